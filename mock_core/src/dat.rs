@@ -0,0 +1,42 @@
+//! `FOpenDat`/`FSaveDat` of the mock core, the layout is in [crate::dat_format].
+
+use crate::dat_format::{read_dat, write_dat, DatRecords};
+use crate::Core;
+
+/// Text of a zero terminated buffer.
+pub(crate) fn c_str(buffer: &[u8; 256]) -> String {
+    let len = buffer.iter().position(|&byte| byte == 0).unwrap_or(buffer.len());
+    String::from_utf8_lossy(&buffer[..len]).into_owned()
+}
+
+/// `FOpenDat` gets the name without extension, so `.mke` and `.fmd` are tried as well.
+pub(crate) fn open(core: &mut Core, dir: &str, name: &str) -> bool {
+    let candidates = [name.to_string(), format!("{name}.mke"), format!("{name}.fmd")];
+    let Some(file) = candidates
+        .iter()
+        .map(|name| std::path::Path::new(dir).join(name))
+        .find(|path| path.is_file()) else {
+        return false
+    };
+    let Ok(bytes) = std::fs::read(file) else {
+        return false
+    };
+    match read_dat(&mut bytes.as_slice()) {
+        Ok(DatRecords { points, prims, regions }) => {
+            core.clear();
+            core.points = points;
+            core.prims = prims;
+            core.regions = regions;
+            true
+        }
+        Err(_) => false
+    }
+}
+
+pub(crate) fn save(core: &Core, dir: &str, name: &str) -> bool {
+    let mut bytes = vec![];
+    if write_dat(&mut bytes, &core.points, &core.prims, &core.regions).is_err() {
+        return false
+    }
+    std::fs::write(std::path::Path::new(dir).join(name), bytes).is_ok()
+}
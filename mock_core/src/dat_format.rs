@@ -0,0 +1,196 @@
+//! Files of `FSaveDat`/`FOpenDat` of the mock core.
+//!
+//! The layout is the mock's own, not the one of FLib: the mock can not open `.mke`/`.fmd` files of FLib
+//! and FLib can not open the files of the mock. It only lets the editor save and open a model through a loaded core.
+//!
+//! Little-endian, every record written field by field without padding:
+//! `"VRMK"`, `u32` version, then points, prims and regions, each prefixed with an `i32` count.
+//! Files of another layout are refused by the magic and version check.
+
+use std::io::{self, Read, Write};
+use std::ptr::null_mut;
+use crate::borrow_types::*;
+
+pub const MAGIC: &[u8; 4] = b"VRMK";
+pub const VERSION: u32 = 1;
+
+/// Records of a file. The mesh is not kept, the regions have no `Gran`.
+#[derive(Debug, Default)]
+pub struct DatRecords {
+    pub points: Vec<TBPoint>,
+    pub prims: Vec<TPrimitive>,
+    pub regions: Vec<TRegion>
+}
+
+pub fn write_dat(out: &mut impl Write, points: &[TBPoint], prims: &[TPrimitive], regions: &[TRegion]) -> io::Result<()> {
+    out.write_all(MAGIC)?;
+    out.write_all(&VERSION.to_le_bytes())?;
+
+    write_count(out, points.len())?;
+    for point in points {
+        for value in [point.x, point.y, point.r] {
+            out.write_all(&value.to_le_bytes())?;
+        }
+        out.write_all(&[point.TypPoint])?;
+        out.write_all(&point.Vp.to_le_bytes())?;
+        out.write_all(&point.Ip.to_le_bytes())?;
+        out.write_all(&point.NNode.to_le_bytes())?;
+    }
+
+    write_count(out, prims.len())?;
+    for prim in prims {
+        for p in prim.p {
+            out.write_all(&p.to_le_bytes())?;
+        }
+        out.write_all(&[prim.TypPrim, prim.IsFront as u8])?;
+        out.write_all(&prim.Vp.to_le_bytes())?;
+        out.write_all(&prim.Ip.to_le_bytes())?;
+    }
+
+    write_count(out, regions.len())?;
+    for region in regions {
+        for value in [region.x, region.y, region.R] {
+            out.write_all(&value.to_le_bytes())?;
+        }
+        out.write_all(&[region.TriW as u8])?;
+        out.write_all(&region.CNu.to_le_bytes())?;
+        out.write_all(&[region.PrMag])?;
+        for value in [region.Px, region.Py, region.W, region.Ip, region.ST, region.PLOT] {
+            out.write_all(&value.to_le_bytes())?;
+        }
+        out.write_all(&[region.JbyIW as u8])?;
+        out.write_all(&region.MatCharName)?;
+    }
+    Ok(())
+}
+
+fn write_count(out: &mut impl Write, len: usize) -> io::Result<()> {
+    let count = i32::try_from(len).map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "too many records"))?;
+    out.write_all(&count.to_le_bytes())
+}
+
+fn read_array<const N: usize>(input: &mut impl Read) -> io::Result<[u8; N]> {
+    let mut buffer = [0u8; N];
+    input.read_exact(&mut buffer)?;
+    Ok(buffer)
+}
+
+fn read_f64(input: &mut impl Read) -> io::Result<f64> {
+    read_array(input).map(f64::from_le_bytes)
+}
+
+fn read_i32(input: &mut impl Read) -> io::Result<i32> {
+    read_array(input).map(i32::from_le_bytes)
+}
+
+fn read_u8(input: &mut impl Read) -> io::Result<u8> {
+    read_array::<1>(input).map(|byte| byte[0])
+}
+
+fn read_count(input: &mut impl Read) -> io::Result<usize> {
+    usize::try_from(read_i32(input)?).map_err(|_| io::Error::new(io::ErrorKind::InvalidData, "negative count"))
+}
+
+pub fn read_dat(input: &mut impl Read) -> io::Result<DatRecords> {
+    if &read_array::<4>(input)? != MAGIC {
+        return Err(io::Error::new(io::ErrorKind::InvalidData, "no VRMK header, the layout is unknown"))
+    }
+    let version = u32::from_le_bytes(read_array(input)?);
+    if version != VERSION {
+        return Err(io::Error::new(io::ErrorKind::InvalidData, format!("version {version} is not supported, only {VERSION}")))
+    }
+
+    let mut points = vec![];
+    for _ in 0..read_count(input)? {
+        points.push(TBPoint {
+            x: read_f64(input)?,
+            y: read_f64(input)?,
+            r: read_f64(input)?,
+            TypPoint: read_u8(input)?,
+            Vp: read_f64(input)?,
+            Ip: read_f64(input)?,
+            NNode: read_i32(input)?
+        });
+    }
+
+    let mut prims = vec![];
+    for _ in 0..read_count(input)? {
+        prims.push(TPrimitive {
+            p: [read_i32(input)?, read_i32(input)?, read_i32(input)?],
+            TypPrim: read_u8(input)?,
+            IsFront: read_u8(input)? != 0,
+            Vp: read_f64(input)?,
+            Ip: read_f64(input)?
+        });
+    }
+
+    let mut regions = vec![];
+    for _ in 0..read_count(input)? {
+        regions.push(TRegion {
+            x: read_f64(input)?,
+            y: read_f64(input)?,
+            R: read_f64(input)?,
+            TriW: read_u8(input)? != 0,
+            CNu: read_f64(input)?,
+            PrMag: read_u8(input)?,
+            Px: read_f64(input)?,
+            Py: read_f64(input)?,
+            W: read_f64(input)?,
+            Ip: read_f64(input)?,
+            ST: read_f64(input)?,
+            PLOT: read_f64(input)?,
+            JbyIW: read_u8(input)? != 0,
+            MatCharName: read_array(input)?,
+            KGran: 0,
+            Gran: null_mut(),
+            vGranMemo: 0,
+            NMatChar: 0
+        });
+    }
+    Ok(DatRecords { points, prims, regions })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn records() -> DatRecords {
+        let mut region = TRegion {
+            x: 1.5, y: -2., R: 20., TriW: true, CNu: 0.25, PrMag: 3, Px: 1., Py: 2., W: 3., Ip: 4., ST: 5., PLOT: 1.1, JbyIW: true,
+            MatCharName: [0; 40], KGran: 0, Gran: null_mut(), vGranMemo: 0, NMatChar: 0
+        };
+        region.set_mat_char_name("2013");
+        DatRecords {
+            points: vec![
+                TBPoint { x: 0.1 + 0.2, y: -1e-9, r: 20., TypPoint: 1, Vp: 0.5, Ip: 2., NNode: 7 },
+                TBPoint { x: 1. / 3., y: 1e300, r: 0., ..Default::default() }
+            ],
+            prims: vec![TPrimitive { p: [0, 1, -1], TypPrim: 2, IsFront: true, Vp: 0.75, Ip: -1. }],
+            regions: vec![region]
+        }
+    }
+
+    #[test]
+    fn read_dat_gives_back_what_write_dat_wrote() {
+        let records = records();
+        let mut bytes = vec![];
+        write_dat(&mut bytes, &records.points, &records.prims, &records.regions).unwrap();
+        let read = read_dat(&mut bytes.as_slice()).unwrap();
+        assert_eq!(format!("{read:?}"), format!("{records:?}"));
+    }
+
+    #[test]
+    fn other_layouts_and_cut_files_are_refused() {
+        let records = records();
+        let mut bytes = vec![];
+        write_dat(&mut bytes, &records.points, &records.prims, &records.regions).unwrap();
+
+        let mut wrong_magic = bytes.clone();
+        wrong_magic[0] = b'X';
+        assert!(read_dat(&mut wrong_magic.as_slice()).is_err());
+        let mut wrong_version = bytes.clone();
+        wrong_version[4..8].copy_from_slice(&(VERSION + 1).to_le_bytes());
+        assert!(read_dat(&mut wrong_version.as_slice()).is_err());
+        assert!(read_dat(&mut &bytes[..bytes.len() - 1]).is_err());
+    }
+}
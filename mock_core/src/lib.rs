@@ -0,0 +1,361 @@
+//! Pure-Rust stand-in for FLib.
+//!
+//! Exports the same functions as the real math core with the same `#[repr(C)]` records,
+//! so the editor can load `libmock_core.so` (or `mock_core.dll`) through `--core`
+//! and run every borrowed code path without the proprietary library.
+//! The `old_flib` feature leaves out the optional symbols, like older FLib builds.
+#![allow(non_snake_case, private_interfaces)]
+
+#[allow(dead_code)]
+#[path = "../../src/model/borrow_types.rs"]
+mod borrow_types;
+mod dat_format;
+mod mesh;
+mod dat;
+
+use std::ptr::{addr_of, null_mut};
+use std::sync::{Mutex, MutexGuard};
+use borrow_types::*;
+
+/// State of the mock core.
+///
+/// Every `p_*` field is the array variable handed out by `FGetP*`,
+/// it always equals the data pointer of the matching `Vec`.
+struct Core {
+    points: Vec<TBPoint>,
+    p_points: *mut TBPoint,
+    prims: Vec<TPrimitive>,
+    p_prims: *mut TPrimitive,
+    nodes: Vec<TNode>,
+    p_nodes: *mut TNode,
+    elems: Vec<TElement>,
+    p_elems: *mut TElement,
+    regions: Vec<TRegion>,
+    p_regions: *mut TRegion,
+
+    /// Storage behind `TNode::NSW`.
+    node_links: Vec<Vec<i32>>,
+    /// Storage behind `TRegion::Gran`.
+    grans: Vec<Vec<TGran>>,
+    /// Bx, By, Bm of every element.
+    field: Vec<(f64, f64, f64)>
+}
+
+/// The raw pointers only point into the vectors of the same [Core].
+unsafe impl Send for Core {}
+
+impl Core {
+    const fn new() -> Self {
+        Self {
+            points: Vec::new(),
+            p_points: null_mut(),
+            prims: Vec::new(),
+            p_prims: null_mut(),
+            nodes: Vec::new(),
+            p_nodes: null_mut(),
+            elems: Vec::new(),
+            p_elems: null_mut(),
+            regions: Vec::new(),
+            p_regions: null_mut(),
+            node_links: Vec::new(),
+            grans: Vec::new(),
+            field: Vec::new()
+        }
+    }
+
+    /// Points every array variable at the current data.
+    fn sync(&mut self) {
+        self.p_points = self.points.as_mut_ptr();
+        self.p_prims = self.prims.as_mut_ptr();
+        self.p_nodes = self.nodes.as_mut_ptr();
+        self.p_elems = self.elems.as_mut_ptr();
+        self.p_regions = self.regions.as_mut_ptr();
+    }
+
+    /// Forgets the mesh and everything calculated on it.
+    fn clear_mesh(&mut self) {
+        self.nodes.clear();
+        self.elems.clear();
+        self.node_links.clear();
+        self.field.clear();
+        self.grans.clear();
+        for region in self.regions.iter_mut() {
+            region.KGran = 0;
+            region.Gran = null_mut();
+        }
+    }
+
+    fn clear(&mut self) {
+        self.clear_mesh();
+        self.points.clear();
+        self.prims.clear();
+        self.regions.clear();
+        self.sync();
+    }
+
+    /// Distance within which `FGet*XY` consider an entity hit.
+    fn tolerance(&self) -> f64 {
+        let Some(first) = self.points.first() else {
+            return 1.0
+        };
+        let (mut min, mut max) = ((first.x, first.y), (first.x, first.y));
+        for point in &self.points {
+            min = (min.0.min(point.x), min.1.min(point.y));
+            max = (max.0.max(point.x), max.1.max(point.y));
+        }
+        (f64::hypot(max.0 - min.0, max.1 - min.1) / 100.0).max(1.0)
+    }
+}
+
+static CORE: Mutex<Core> = Mutex::new(Core::new());
+
+fn core() -> MutexGuard<'static, Core> {
+    CORE.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+/// Distance from `p` to the segment `a`-`b`.
+fn distance_to_segment(p: (f64, f64), a: (f64, f64), b: (f64, f64)) -> f64 {
+    let ab = (b.0 - a.0, b.1 - a.1);
+    let len = ab.0 * ab.0 + ab.1 * ab.1;
+    let t = if len == 0.0 { 0.0 } else { (((p.0 - a.0) * ab.0 + (p.1 - a.1) * ab.1) / len).clamp(0.0, 1.0) };
+    f64::hypot(p.0 - a.0 - t * ab.0, p.1 - a.1 - t * ab.1)
+}
+
+#[no_mangle]
+pub extern "C" fn FInitModel() {
+    core().clear()
+}
+
+#[no_mangle]
+pub extern "C" fn FDelMemoModel() {
+    core().clear()
+}
+
+#[no_mangle]
+pub extern "C" fn FCreatePoint(x: &f64, y: &f64, r: &f64, typ_point: &u8) -> i32 {
+    let mut core = core();
+    core.points.push(TBPoint { x: *x, y: *y, r: *r, TypPoint: *typ_point, Vp: 0.0, Ip: 0.0, NNode: -1 });
+    core.sync();
+    core.points.len() as i32 - 1
+}
+
+/// Removes the point and shifts the references of every prim after it.
+#[no_mangle]
+pub extern "C" fn FDelPoint(index: i32) -> bool {
+    let mut core = core();
+    if index < 0 || index as usize >= core.points.len() {
+        return false
+    }
+    core.points.remove(index as usize);
+    for prim in core.prims.iter_mut() {
+        for p in prim.p.iter_mut() {
+            if *p > index {
+                *p -= 1
+            }
+        }
+    }
+    core.sync();
+    true
+}
+
+#[no_mangle]
+pub extern "C" fn FCreatePrim(p1: &i32, p2: &i32, p3: &i32, typ_prim: &u8, vp: &f64) -> i32 {
+    let mut core = core();
+    core.prims.push(TPrimitive { p: [*p1, *p2, *p3], TypPrim: *typ_prim, IsFront: false, Vp: *vp, Ip: 0.0 });
+    core.sync();
+    core.prims.len() as i32 - 1
+}
+
+#[no_mangle]
+pub extern "C" fn FDelPrim(index: i32) -> bool {
+    let mut core = core();
+    if index < 0 || index as usize >= core.prims.len() {
+        return false
+    }
+    core.prims.remove(index as usize);
+    core.sync();
+    true
+}
+
+/// `mat_char_name` is a zero terminated buffer, like the paths of `FOpenDat`.
+#[no_mangle]
+#[allow(clippy::too_many_arguments)]
+pub extern "C" fn FCreateRegion(x: &f64, y: &f64, r: &f64, tri_w: &bool, pr_mag: &u8,
+                                mat_char_name: &[u8; 256], mu: &f64, px: &f64, py: &f64, w: &f64, ip: &f64, plot: &f64,
+                                j_by_iw: &bool) -> i32 {
+    let mut region = TRegion {
+        x: *x, y: *y, R: *r,
+        TriW: *tri_w,
+        CNu: 1.0 / *mu,
+        PrMag: *pr_mag,
+        Px: *px, Py: *py,
+        W: *w, Ip: *ip,
+        ST: 0.0,
+        PLOT: *plot,
+        JbyIW: *j_by_iw,
+        MatCharName: [0; 40],
+        KGran: 0,
+        Gran: null_mut(),
+        vGranMemo: 0,
+        NMatChar: 0
+    };
+    region.set_mat_char_name(&dat::c_str(mat_char_name));
+    let mut core = core();
+    core.regions.push(region);
+    core.sync();
+    core.regions.len() as i32 - 1
+}
+
+#[no_mangle]
+pub extern "C" fn FBuildFM() -> bool {
+    let mut core = core();
+    let built = mesh::build(&mut core);
+    core.sync();
+    built
+}
+
+#[no_mangle]
+pub extern "C" fn FGetBxByBm(index: i32, bx: &mut f64, by: &mut f64, bm: &mut f64) {
+    let core = core();
+    if let Some(&(x, y, m)) = usize::try_from(index).ok().and_then(|index| core.field.get(index)) {
+        (*bx, *by, *bm) = (x, y, m)
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn FGetPPoints() -> *const *mut TBPoint {
+    addr_of!(core().p_points)
+}
+
+#[no_mangle]
+pub extern "C" fn FGetNPoint() -> i32 {
+    core().points.len() as i32
+}
+
+#[no_mangle]
+pub extern "C" fn FGetPPrim() -> *const *mut TPrimitive {
+    addr_of!(core().p_prims)
+}
+
+#[no_mangle]
+pub extern "C" fn FGetNPrim() -> i32 {
+    core().prims.len() as i32
+}
+
+#[no_mangle]
+pub extern "C" fn FGetPNode() -> *const *mut TNode {
+    addr_of!(core().p_nodes)
+}
+
+#[no_mangle]
+pub extern "C" fn FGetNNode() -> i32 {
+    core().nodes.len() as i32
+}
+
+#[no_mangle]
+pub extern "C" fn FGetPElem() -> *const *mut TElement {
+    addr_of!(core().p_elems)
+}
+
+#[no_mangle]
+pub extern "C" fn FGetNElem() -> i32 {
+    core().elems.len() as i32
+}
+
+#[no_mangle]
+pub extern "C" fn FGetPReg() -> *const *mut TRegion {
+    addr_of!(core().p_regions)
+}
+
+#[cfg(not(feature = "old_flib"))]
+#[no_mangle]
+pub extern "C" fn FGetNReg() -> i32 {
+    core().regions.len() as i32
+}
+
+#[no_mangle]
+pub extern "C" fn FOpenDat(dir: &[u8; 256], name: &[u8; 256]) -> bool {
+    let mut core = core();
+    let opened = dat::open(&mut core, &dat::c_str(dir), &dat::c_str(name));
+    core.sync();
+    opened
+}
+
+#[no_mangle]
+pub extern "C" fn FSaveDat(dir: &[u8; 256], name: &[u8; 256]) -> bool {
+    dat::save(&core(), &dat::c_str(dir), &dat::c_str(name))
+}
+
+#[no_mangle]
+pub extern "C" fn FSetXPoint(index: i32, x: f64) {
+    let mut core = core();
+    if let Some(point) = usize::try_from(index).ok().and_then(|index| core.points.get_mut(index)) {
+        point.x = x
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn FSetYPoint(index: i32, y: f64) {
+    let mut core = core();
+    if let Some(point) = usize::try_from(index).ok().and_then(|index| core.points.get_mut(index)) {
+        point.y = y
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn FGetPointXY(x: f64, y: f64) -> i32 {
+    let core = core();
+    let tolerance = core.tolerance();
+    core.points
+        .iter()
+        .position(|point| f64::hypot(point.x - x, point.y - y) < tolerance)
+        .map_or(-1, |index| index as i32)
+}
+
+#[no_mangle]
+pub extern "C" fn FGetPrimXY(x: f64, y: f64) -> i32 {
+    let core = core();
+    let tolerance = core.tolerance();
+    let point = |index: i32| core.points.get(index as usize).map(|p| (p.x, p.y));
+    core.prims
+        .iter()
+        .position(|prim| {
+            let (Some(a), Some(b)) = (point(prim.p[0]), point(prim.p[1])) else {
+                return false
+            };
+            if prim.p[2] == -1 {
+                distance_to_segment((x, y), a, b) < tolerance
+            } else if let Some(c) = point(prim.p[2]) {
+                let radius = f64::hypot(a.0 - c.0, a.1 - c.1);
+                (f64::hypot(x - c.0, y - c.1) - radius).abs() < tolerance
+            } else {
+                false
+            }
+        })
+        .map_or(-1, |index| index as i32)
+}
+
+#[no_mangle]
+pub extern "C" fn FGetNodeXY(x: f64, y: f64) -> i32 {
+    let core = core();
+    let tolerance = core.tolerance();
+    core.nodes
+        .iter()
+        .enumerate()
+        .map(|(index, node)| (index, f64::hypot(node.x - x, node.y - y)))
+        .filter(|&(_, distance)| distance < tolerance)
+        .min_by(|a, b| a.1.total_cmp(&b.1))
+        .map_or(-1, |(index, _)| index as i32)
+}
+
+#[no_mangle]
+pub extern "C" fn FGetRegXY(x: f64, y: f64) -> i32 {
+    let core = core();
+    if let Some(elem) = mesh::find_elem(&core, x, y) {
+        return core.elems[elem].IZP as i32
+    }
+    core.regions
+        .iter()
+        .position(|region| f64::hypot(region.x - x, region.y - y) < region.R)
+        .map_or(-1, |index| index as i32)
+}
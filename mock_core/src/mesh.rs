@@ -0,0 +1,220 @@
+//! Structured triangulation of the model's bounding box and a made-up field on it.
+//!
+//! Good enough to exercise every array the editor reads, it does not follow the prims.
+
+use std::collections::BTreeSet;
+use std::ptr::null_mut;
+use crate::borrow_types::*;
+use crate::Core;
+
+/// Cells along the longer side of the bounding box.
+const CELLS: usize = 24;
+
+pub(crate) fn build(core: &mut Core) -> bool {
+    core.clear_mesh();
+    let Some(first) = core.points.first() else {
+        return false
+    };
+    let (mut min, mut max) = ((first.x, first.y), (first.x, first.y));
+    for point in &core.points {
+        min = (min.0.min(point.x), min.1.min(point.y));
+        max = (max.0.max(point.x), max.1.max(point.y));
+    }
+    let (width, height) = (max.0 - min.0, max.1 - min.1);
+    if width <= 0.0 || height <= 0.0 {
+        return false
+    }
+    let step = width.max(height) / CELLS as f64;
+    let nx = ((width / step).round() as usize).max(1);
+    let ny = ((height / step).round() as usize).max(1);
+    let (dx, dy) = (width / nx as f64, height / ny as f64);
+
+    for j in 0..=ny {
+        for i in 0..=nx {
+            let (x, y) = (min.0 + dx * i as f64, min.1 + dy * j as f64);
+            let on_border = i == 0 || j == 0 || i == nx || j == ny;
+            core.nodes.push(TNode {
+                x, y,
+                VP: potential(&core.regions, x, y),
+                TypNode: on_border as u8,
+                PNode: 0,
+                KolSW: 0,
+                NSW: null_mut(),
+                KolSI: 0,
+                NSI: null_mut(),
+                NSK: null_mut(),
+                vKolSWMemo: 0,
+                vKolSIMemo: 0,
+                F: 0.0,
+                Yp: 0.0
+            });
+        }
+    }
+
+    let node = |i: usize, j: usize| (j * (nx + 1) + i) as i32;
+    for j in 0..ny {
+        for i in 0..nx {
+            let (a, b, c, d) = (node(i, j), node(i + 1, j), node(i + 1, j + 1), node(i, j + 1));
+            push_elem(core, [a, b, c]);
+            push_elem(core, [a, c, d]);
+        }
+    }
+
+    link_nodes(core);
+    find_borders(core);
+    true
+}
+
+/// Sum of a bump of `W * Ip` around every region, or a uniform field without regions.
+fn potential(regions: &[TRegion], x: f64, y: f64) -> f64 {
+    if regions.is_empty() {
+        return y
+    }
+    regions
+        .iter()
+        .map(|region| {
+            let r = region.R.max(1.0) * 4.0;
+            let d2 = (x - region.x).powi(2) + (y - region.y).powi(2);
+            region.W * region.Ip * r * (-d2 / (r * r)).exp()
+        })
+        .sum()
+}
+
+fn push_elem(core: &mut Core, m: [i32; 3]) {
+    let p = m.map(|index| {
+        let node = &core.nodes[index as usize];
+        (node.x, node.y)
+    });
+    // Linear shape functions N = (A + B x + C y) / (2 Delta).
+    let a = [0, 1, 2].map(|k| {
+        let (j, l) = ((k + 1) % 3, (k + 2) % 3);
+        p[j].0 * p[l].1 - p[l].0 * p[j].1
+    });
+    let b = [0, 1, 2].map(|k| p[(k + 1) % 3].1 - p[(k + 2) % 3].1);
+    let c = [0, 1, 2].map(|k| p[(k + 2) % 3].0 - p[(k + 1) % 3].0);
+    let delta = (b[0] * c[1] - b[1] * c[0]) / 2.0;
+    let (xs, ys) = ((p[0].0 + p[1].0 + p[2].0) / 3.0, (p[0].1 + p[1].1 + p[2].1) / 3.0);
+
+    let vp = m.map(|index| core.nodes[index as usize].VP);
+    let bx = (0..3).map(|k| c[k] * vp[k]).sum::<f64>() / (2.0 * delta);
+    let by = -(0..3).map(|k| b[k] * vp[k]).sum::<f64>() / (2.0 * delta);
+
+    let region = core.regions
+        .iter()
+        .enumerate()
+        .min_by(|(_, r1), (_, r2)| {
+            let d1 = (r1.x - xs).powi(2) + (r1.y - ys).powi(2);
+            let d2 = (r2.x - xs).powi(2) + (r2.y - ys).powi(2);
+            d1.total_cmp(&d2)
+        })
+        .map(|(index, region)| (index as i16, region.Px, region.Py));
+    let (izp, px, py) = region.unwrap_or((-1, 1.0, 1.0));
+
+    core.elems.push(TElement {
+        m,
+        IZP: izp,
+        Px: px,
+        Py: py,
+        XNJU: 0.0,
+        A1: 0.0,
+        Delta: delta,
+        xs, ys,
+        S: [0.0; 6],
+        A: a,
+        B: b,
+        C: c
+    });
+    core.field.push((bx, by, f64::hypot(bx, by)));
+}
+
+/// Fills `NSW` of every node with its neighbours along element edges.
+fn link_nodes(core: &mut Core) {
+    let mut links: Vec<BTreeSet<i32>> = vec![BTreeSet::new(); core.nodes.len()];
+    for elem in &core.elems {
+        for k in 0..3 {
+            let (a, b) = (elem.m[k], elem.m[(k + 1) % 3]);
+            links[a as usize].insert(b);
+            links[b as usize].insert(a);
+        }
+    }
+    core.node_links = links.into_iter().map(|set| set.into_iter().collect()).collect();
+    for (node, links) in core.nodes.iter_mut().zip(core.node_links.iter_mut()) {
+        node.KolSW = links.len() as i32;
+        node.vKolSWMemo = links.len() as i32;
+        node.NSW = links.as_mut_ptr();
+    }
+}
+
+/// Fills `Gran` of every region with the border nodes of its elements,
+/// ordered by the angle around the region marker.
+fn find_borders(core: &mut Core) {
+    let mut grans = vec![];
+    for (index, region) in core.regions.iter().enumerate() {
+        let elems: Vec<&TElement> = core.elems.iter().filter(|elem| elem.IZP as usize == index).collect();
+        let mut edges: Vec<(i32, i32)> = vec![];
+        for elem in &elems {
+            for k in 0..3 {
+                let (a, b) = (elem.m[k], elem.m[(k + 1) % 3]);
+                edges.push((a.min(b), a.max(b)));
+            }
+        }
+        edges.sort_unstable();
+        let mut border = BTreeSet::new();
+        let mut i = 0;
+        while i < edges.len() {
+            if i + 1 < edges.len() && edges[i] == edges[i + 1] {
+                i += 2;
+                continue
+            }
+            border.insert(edges[i].0);
+            border.insert(edges[i].1);
+            i += 1;
+        }
+
+        let mut gran: Vec<TGran> = border
+            .into_iter()
+            .map(|node| TGran { Node: node, UgUzl: inner_angle(core, &elems, node) })
+            .collect();
+        gran.sort_by(|a, b| {
+            let angle = |gran: &TGran| {
+                let node = &core.nodes[gran.Node as usize];
+                (node.y - region.y).atan2(node.x - region.x)
+            };
+            angle(a).total_cmp(&angle(b))
+        });
+        grans.push(gran);
+    }
+
+    core.grans = grans;
+    for (region, gran) in core.regions.iter_mut().zip(core.grans.iter_mut()) {
+        region.KGran = gran.len() as i32;
+        region.vGranMemo = gran.len() as i32;
+        region.Gran = gran.as_mut_ptr();
+    }
+}
+
+/// Sum of the angles at `node` of the elements around it.
+fn inner_angle(core: &Core, elems: &[&TElement], node: i32) -> f64 {
+    let point = |index: i32| {
+        let node = &core.nodes[index as usize];
+        (node.x, node.y)
+    };
+    elems
+        .iter()
+        .filter_map(|elem| {
+            let k = elem.m.iter().position(|&m| m == node)?;
+            let (o, a, b) = (point(node), point(elem.m[(k + 1) % 3]), point(elem.m[(k + 2) % 3]));
+            let (u, v) = ((a.0 - o.0, a.1 - o.1), (b.0 - o.0, b.1 - o.1));
+            Some((u.0 * v.1 - u.1 * v.0).abs().atan2(u.0 * v.0 + u.1 * v.1))
+        })
+        .sum()
+}
+
+/// Element that contains (x, y).
+pub(crate) fn find_elem(core: &Core, x: f64, y: f64) -> Option<usize> {
+    core.elems.iter().position(|elem| {
+        let weights = [0, 1, 2].map(|k| elem.A[k] + elem.B[k] * x + elem.C[k] * y);
+        let sign = elem.Delta.signum();
+        weights.iter().all(|&w| w * sign >= 0.0)
+    })
+}
@@ -1,13 +1,26 @@
-use std::rc::Rc;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 use iced::Point;
 use iced::widget::text_editor;
-use libloading::Library;
 use crate::app::undo_manager::UndoManager;
 use crate::app_config::AppConfig;
+use crate::app_config::settings::{discover_core, CoreSource, Settings};
 use crate::model::framework::State;
 use crate::model::Model;
+use crate::model::boundary::Boundary;
+use crate::model::validate::Diagnostic;
+use crate::math_core::{MathCore, RegionParams};
 
-pub(super) struct CallByName {
+/// Mesh build running on a worker thread.
+pub(crate) struct CoreRun {
+    /// The borrowed model, put back once the run finishes. [VecRed::model] shows a snapshot meanwhile.
+    pub model: Model,
+    pub started: Instant,
+    pub elapsed: Duration,
+    pub cancelled: bool
+}
+
+pub(crate) struct CallByName {
     pub prim: i32,
     pub node: i32, 
     pub region: i32
@@ -24,18 +37,51 @@ pub struct VecRed {
     /// Point, radius, number in points.
     pub chosen_point: Option<(Point, f32, usize)>,
     pub chosen_elems: Option<CallByName>,
+    /// Prims picked in "Boundary" mode.
+    pub chosen_prims: Vec<usize>,
+    pub boundary: Boundary,
+    pub boundary_value: String,
+    /// Problems found by the last check of the model, see [Model::validate].
+    pub diagnostics: Vec<Diagnostic>,
     
     /// Can be 3 or 0, depends on [chosen_point].
     pub point_string: Vec<String>,
+    /// Form for the next region, one value per [crate::math_core::region::REGION_FIELDS].
+    pub region_string: Vec<String>,
 
-    pub modes: [&'static str; 6],
+    pub modes: [&'static str; 7],
     pub mode: &'static str,
 
     pub app_config: AppConfig,
     pub scale: f32,
     pub default_circle: f32,
 
-    pub lib: Option<Rc<Library>>
+    pub core: Option<Arc<dyn MathCore>>,
+    /// Path to the math core, can be edited in the side panel.
+    pub core_path: text_editor::Content,
+    pub core_source: Option<CoreSource>,
+    /// Result of the last attempt to open the math core.
+    pub core_status: String,
+    pub settings: Settings,
+    /// "Detach" also calls `FDelMemoModel`.
+    pub free_core_on_detach: bool,
+    /// Some while the math core is busy, see [CoreRun].
+    pub run: Option<CoreRun>
+}
+
+impl VecRed {
+    /// Creates the editor and looks for the math core with [discover_core].
+    pub fn new(args: &[String]) -> Self {
+        let settings = Settings::load();
+        let mut vec_red = Self::default();
+        if let Some((path, source)) = discover_core(args, &settings) {
+            vec_red.core_path = text_editor::Content::with_text(&path);
+            vec_red.core_source = Some(source);
+        }
+        vec_red.region_string = settings.region.to_strings();
+        vec_red.settings = settings;
+        vec_red
+    }
 }
 
 impl Default for VecRed {
@@ -43,13 +89,18 @@ impl Default for VecRed {
         Self {
             journal: UndoManager::default(),
             path_to_load: text_editor::Content::default(),
-            modes: ["Move", "Point", "Line", "Arc", "Region", "Find"],
+            modes: ["Move", "Point", "Line", "Arc", "Region", "Find", "Boundary"],
             mode: "Move",
             
             chosen_point: None,
             chosen_elems: None,
+            chosen_prims: vec![],
+            boundary: Boundary::Dirichlet,
+            boundary_value: String::from("0"),
+            diagnostics: vec![],
             
             point_string: vec![],
+            region_string: RegionParams::default().to_strings(),
             
             state: State::default(),
             model: Model::default(),
@@ -58,7 +109,13 @@ impl Default for VecRed {
             scale: 1.0,
             default_circle: 20.0,
 
-            lib: None
+            core: None,
+            core_path: text_editor::Content::default(),
+            core_source: None,
+            core_status: String::new(),
+            settings: Settings::default(),
+            free_core_on_detach: false,
+            run: None
         }
     }
 }
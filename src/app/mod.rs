@@ -6,4 +6,7 @@ pub mod core;
 mod update_def;
 mod update_lib;
 mod update_input;
-mod view_full_info;
\ No newline at end of file
+mod view_full_info;
+pub mod render;
+#[cfg(test)]
+mod tests;
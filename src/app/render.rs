@@ -0,0 +1,110 @@
+//! Headless rendering: `vec_red --render <model> <out.png>` draws the model to a PNG and exits without a window.
+//!
+//! Options:
+//! - `--size <width>x<height>`: resolution, 1600x1200 by default.
+//! - `--view <x0>,<y0>,<x1>,<y1>`: part of the model to show, the whole model by default.
+//! - `--green <max|auto>`: field plot of Bm up to `max`, `auto` takes the largest Bm of the model.
+//! - `--line <width>`: width of the lines, 1 by default.
+
+use iced::{Point, Size};
+use crate::app_config::AppConfig;
+use crate::app_config::app_config::NodeMode;
+use crate::model::{dxf, load_model, painter, png, project, Model};
+
+/// Command line flag that renders a model instead of starting the editor.
+pub const RENDER_FLAG: &str = "--render";
+
+/// Circle of the points read from DXF, as in the editor.
+const DEFAULT_CIRCLE: f32 = 20.0;
+
+/// The arguments ask for [run].
+pub fn is_render(args: &[String]) -> bool {
+    args.iter().any(|arg| arg == RENDER_FLAG)
+}
+
+/// Renders and returns the exit code of the process.
+pub fn run(args: &[String]) -> i32 {
+    match render(args) {
+        Ok(out) => {
+            println!("Written {out}");
+            0
+        }
+        Err(error) => {
+            eprintln!("{error}");
+            eprintln!("Usage: vec_red {RENDER_FLAG} <model> <out.png> [--size 1600x1200] [--view x0,y0,x1,y1] [--green max|auto] [--line 1]");
+            2
+        }
+    }
+}
+
+fn render(args: &[String]) -> Result<String, String> {
+    let position = args.iter().position(|arg| arg == RENDER_FLAG).unwrap_or_default();
+    let (Some(input), Some(out)) = (args.get(position + 1), args.get(position + 2)) else {
+        return Err(String::from("The model and the PNG file are missing"))
+    };
+    let option = |name: &str| args.iter().position(|arg| arg == name).and_then(|index| args.get(index + 1));
+
+    let size = match option("--size") {
+        Some(size) => {
+            let (width, height) = size.split_once('x').ok_or_else(|| format!("--size {size} is not <width>x<height>"))?;
+            Size::new(parse_number(width, "--size")?, parse_number(height, "--size")?)
+        }
+        None => Size::new(1600., 1200.)
+    };
+    let line = option("--line").map_or(Ok(1.), |line| parse_number(line, "--line"))?;
+
+    let (model, mut app_config) = open(input)?;
+
+    if let Some(green) = option("--green") {
+        if !model.has_field() {
+            return Err(format!("{input} has no field to plot"))
+        }
+        let max = if green == "auto" {
+            (0..model.elems_len()).filter_map(|index| model.get_bm_only(index)).fold(0., f32::max)
+        } else {
+            parse_number(green, "--green")?
+        };
+        app_config.node_mode = NodeMode::Green { max: if max > 0. { max } else { 1. } };
+    }
+
+    let view = match option("--view") {
+        Some(view) => {
+            let numbers = view.split(',').map(|number| parse_number(number, "--view")).collect::<Result<Vec<f32>, String>>()?;
+            let [x0, y0, x1, y1] = numbers[..] else {
+                return Err(format!("--view {view} is not <x0>,<y0>,<x1>,<y1>"))
+            };
+            let (min, max) = (Point::new(x0.min(x1), y0.min(y1)), Point::new(x0.max(x1), y0.max(y1)));
+            painter::fit_view(&app_config, min, max, size)
+        }
+        None => {
+            let (min, max) = painter::whole_model_bounds(&model, &app_config);
+            painter::fit_view(&app_config, min, max, size)
+        }
+    };
+
+    png::save_png(out, &model, line, &view)?;
+    Ok(out.clone())
+}
+
+fn parse_number(text: &str, option: &str) -> Result<f32, String> {
+    text.trim().parse().map_err(|_| format!("{option}: {text} is not a number"))
+}
+
+/// Opens any file the editor opens, without a core. A project brings its view.
+fn open(path: &str) -> Result<(Model, AppConfig), String> {
+    let mut app_config = AppConfig::default();
+    let model = if project::is_project(path) {
+        let (model, view) = project::open_project(path)?;
+        app_config.apply_view_state(&view);
+        model
+    } else if dxf::is_dxf(path) {
+        dxf::open_dxf(path, DEFAULT_CIRCLE)?
+    } else {
+        let mut model = Model::default();
+        if !load_model::open_model(&None, path.to_string(), &mut model) {
+            return Err(format!("{path} is not opened"))
+        }
+        model
+    };
+    Ok((model, app_config))
+}
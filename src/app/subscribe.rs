@@ -1,4 +1,7 @@
+use std::time::Duration;
 use iced::{keyboard, Subscription, Vector};
+use iced::futures::channel::mpsc;
+use iced::futures::Stream;
 use iced::keyboard::Key;
 use iced::keyboard::key::Named;
 use crate::{Message, VecRed};
@@ -11,7 +14,27 @@ impl VecRed {
         let resize_event = iced::window::resize_events().map(|(_id, size)|{
             Message::WindowResized(size)
         });
-        Subscription::batch(vec![keyboard_events, resize_event])
+        let mut subscriptions = vec![keyboard_events, resize_event];
+        if self.run.is_some() {
+            subscriptions.push(iced::window::frames().map(Message::RunTick));
+        }
+        if self.core.as_ref().is_some_and(|core| core.is_alive()) {
+            subscriptions.push(Subscription::run(Self::core_watch));
+        }
+        Subscription::batch(subscriptions)
+    }
+
+    /// [Message::CheckCore] every second, so a crashed core host is noticed without any input.
+    /// The ticks come from a thread, which ends when the subscription is dropped.
+    fn core_watch() -> impl Stream<Item = Message> {
+        let (mut sender, receiver) = mpsc::channel(1);
+        std::thread::spawn(move || {
+            while !sender.is_closed() {
+                std::thread::sleep(Duration::from_secs(1));
+                let _ = sender.try_send(Message::CheckCore);
+            }
+        });
+        receiver
     }
 
     fn shortcuts (key: Key, modifiers: keyboard::Modifiers) -> Option<Message> {
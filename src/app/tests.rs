@@ -0,0 +1,265 @@
+//! The editor against `mock_core`: loaded into this process with [crate::math_core::LibCore] and run in a core host with [ProcessCore].
+//!
+//! The tests build `mock_core` and the editor executable with cargo first, the host needs the editor's `--core-host`.
+
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::{Arc, Mutex, MutexGuard, OnceLock};
+use iced::{Color, Point};
+use iced::widget::canvas;
+use crate::{Message, VecRed};
+use crate::app_config::app_config::NodeMode;
+use crate::math_core::{MathCore, ProcessCore};
+use crate::model::painter::Painter;
+
+/// Directory with the built `mock_core` and editor, next to the test binary.
+fn target_dir() -> &'static Path {
+    static DIR: OnceLock<PathBuf> = OnceLock::new();
+    DIR.get_or_init(|| {
+        let mut build = Command::new(env!("CARGO"));
+        build.args(["build", "-q", "-p", "mock_core", "-p", "vec_red"]).current_dir(env!("CARGO_MANIFEST_DIR"));
+        if !cfg!(debug_assertions) {
+            build.arg("--release");
+        }
+        assert!(build.status().expect("cargo is not started").success(), "mock_core is not built");
+        // target/<profile>/deps/<test binary>
+        let exe = std::env::current_exe().expect("no test executable");
+        exe.parent().and_then(Path::parent).expect("no target directory").to_path_buf()
+    })
+}
+
+fn mock_core_path() -> String {
+    let name = format!("{}mock_core{}", std::env::consts::DLL_PREFIX, std::env::consts::DLL_SUFFIX);
+    target_dir().join(name).to_string_lossy().into_owned()
+}
+
+/// `mock_core` built with `old_flib`, in its own target directory so it does not replace [mock_core_path].
+fn old_mock_core_path() -> String {
+    static PATH: OnceLock<PathBuf> = OnceLock::new();
+    let path = PATH.get_or_init(|| {
+        let dir = target_dir().join("old_flib");
+        let mut build = Command::new(env!("CARGO"));
+        build.args(["build", "-q", "-p", "mock_core", "--features", "old_flib", "--target-dir"])
+            .arg(&dir)
+            .current_dir(env!("CARGO_MANIFEST_DIR"));
+        if !cfg!(debug_assertions) {
+            build.arg("--release");
+        }
+        assert!(build.status().expect("cargo is not started").success(), "old mock_core is not built");
+        let profile = if cfg!(debug_assertions) { "debug" } else { "release" };
+        let name = format!("{}mock_core{}", std::env::consts::DLL_PREFIX, std::env::consts::DLL_SUFFIX);
+        dir.join(profile).join(name)
+    });
+    path.to_string_lossy().into_owned()
+}
+
+fn host_path() -> PathBuf {
+    target_dir().join(format!("vec_red{}", std::env::consts::EXE_SUFFIX))
+}
+
+/// The library keeps one model per process, the tests that load it here take turns.
+fn in_process() -> MutexGuard<'static, ()> {
+    static LOCK: Mutex<()> = Mutex::new(());
+    LOCK.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+/// Square of 100 by 100 with its four sides, not sent yet.
+fn square() -> VecRed {
+    let mut vec_red = VecRed::default();
+    for (x, y) in [(0., 0.), (100., 0.), (100., 100.), (0., 100.)] {
+        vec_red.model.points_push(Point::new(x, y), 20.).expect("no point");
+    }
+    for prim in [[0, 1, -1], [1, 2, -1], [2, 3, -1], [3, 0, -1]] {
+        vec_red.model.prims_push(prim).expect("no prim");
+    }
+    vec_red
+}
+
+/// Counts what is painted.
+#[derive(Default)]
+struct Count {
+    strokes: usize,
+    fills: usize
+}
+
+impl Painter for Count {
+    fn stroke(&mut self, _path: &canvas::Path, _color: Color, _width: f32) {
+        self.strokes += 1
+    }
+    fn fill(&mut self, _path: &canvas::Path, _color: Color) {
+        self.fills += 1
+    }
+}
+
+/// Deletes a point of the square with its two prims and undoes it, the records come back whole.
+fn delete_and_undo(vec_red: &mut VecRed, num: usize) {
+    let records = |vec_red: &VecRed| format!("{:?}", (
+        vec_red.model.tb_point_ref(num),
+        (0..vec_red.model.prims_len()).map(|index| vec_red.model.t_primitive_ref(index)).collect::<Vec<_>>()
+    ));
+    let before = records(vec_red);
+
+    vec_red.chosen_point = Some((vec_red.model.points(num), vec_red.model.points_r(num), num));
+    let _ = vec_red.update(Message::DeletePoint);
+    assert_eq!((vec_red.model.points_len(), vec_red.model.prims_len()), (3, 2));
+    for _ in 0..3 {
+        let _ = vec_red.update(Message::Undo);
+    }
+    assert_eq!((vec_red.model.points_len(), vec_red.model.prims_len()), (4, 4));
+    assert_eq!(before, records(vec_red));
+}
+
+/// Edits the sent square, meshes it and asks the core for the field and for what is under the cursor.
+fn edit_build_and_query(vec_red: &mut VecRed) {
+    let core = vec_red.core.clone().expect("the core is not kept");
+    assert!(vec_red.model.is_borrowed());
+    assert_eq!((vec_red.model.points_len(), vec_red.model.prims_len()), (4, 4));
+    assert_eq!(vec_red.model.prim_condition(0), (1, 1.));
+
+    // a move goes to the core and comes back from it, edits the core has no call for are refused
+    vec_red.model.point_set(2, Point::new(110., 100.), 20.).expect("the point is not moved");
+    assert!(vec_red.model.point_set(2, Point::new(110., 100.), 25.).is_err());
+    assert!(vec_red.model.set_prim_condition(1, 2, 0.5).is_err());
+    vec_red.model.sync_everything();
+    assert_eq!(vec_red.model.points(2), Point::new(110., 100.));
+    assert_eq!(vec_red.model.points_r(2), 20.);
+    assert_eq!(vec_red.model.prim_condition(1), (1, 1.));
+    assert_eq!(core.get_point_xy(110., 100.), 2);
+    assert!(!vec_red.model.editable_physics());
+    vec_red.chosen_prims = vec![1];
+    let _ = vec_red.update(Message::ApplyBoundary);
+    assert_eq!(vec_red.model.prim_condition(1), (1, 1.));
+    assert!(vec_red.core_status.contains("detach"), "{}", vec_red.core_status);
+
+    vec_red.create_region(Point::new(50., 50.));
+    assert_eq!(vec_red.model.regions_len(), 1);
+    let name = vec_red.model.t_region_ref(0).map(|region| region.mat_char_name());
+    assert_eq!(name.as_deref(), Some(vec_red.region_string[3].trim()));
+
+    // the run the "Create triangle" button starts, without the executor
+    let _ = vec_red.create_triangle();
+    assert!(vec_red.run.is_some());
+    assert!(!vec_red.model.is_borrowed());
+    let built = core.build_fm();
+    vec_red.finish_run(built);
+    assert!(built);
+    assert!(vec_red.model.is_borrowed());
+    assert!(vec_red.model.nodes_len() >= 4);
+    assert!(vec_red.model.elems_len() >= 2);
+    assert!(vec_red.model.has_field());
+    for index in 0..vec_red.model.elems_len() {
+        let (bx, by, bm) = vec_red.model.get_bx_by_bm(index).expect("no field in an element");
+        assert!(bx.is_finite() && by.is_finite() && bm >= 0.);
+        assert_eq!(vec_red.model.get_bm_only(index), Some(bm as f32));
+    }
+
+    // the core has no call to edit records, they stay as they are
+    let mut point = vec_red.model.tb_point_ref(0).cloned().expect("no point");
+    point.TypPoint = 1;
+    assert!(vec_red.model.commit_point(0, &point).is_err());
+    assert_eq!(vec_red.model.tb_point_ref(0).map(|point| point.TypPoint), Some(0));
+    assert!(!vec_red.model.region_gran(0).is_empty());
+    let mut region = vec_red.model.t_region_ref(0).cloned().expect("no region");
+    let r = region.R;
+    region.R = r + 7.;
+    assert!(vec_red.model.commit_region(0, &region).is_err());
+    assert_eq!(vec_red.model.t_region_ref(0).map(|region| region.R), Some(r));
+
+    let _ = vec_red.update(Message::FindEverything(50., 50.));
+    let found = vec_red.chosen_elems.as_ref().expect("nothing is found");
+    assert_eq!(found.region, 0);
+    let _ = vec_red.update(Message::FindEverything(0., 0.));
+    let found = vec_red.chosen_elems.as_ref().expect("nothing is found");
+    assert!(found.node >= 0);
+    assert!(found.prim == 0 || found.prim == 3);
+
+    // Green mode paints from the field of the core
+    let mut app_config = crate::app_config::AppConfig::default();
+    app_config.node_mode = NodeMode::Green { max: 1. };
+    let mut count = Count::default();
+    vec_red.model.draw_model(&mut count, 1., &app_config);
+    assert!(count.fills + count.strokes > vec_red.model.prims_len());
+}
+
+#[test]
+fn lib_core_sends_edits_builds_and_queries() {
+    let path = mock_core_path();
+    let _turn = in_process();
+    let mut vec_red = square();
+    vec_red.core_path = iced::widget::text_editor::Content::with_text(&path);
+    // the settings are not saved when the path is already in them
+    vec_red.settings.core_path = Some(path);
+    let _ = vec_red.update(Message::OpenMathCore);
+    assert!(vec_red.core.is_some(), "{}", vec_red.core_status);
+    edit_build_and_query(&mut vec_red);
+}
+
+#[test]
+fn process_core_sends_edits_builds_and_queries() {
+    let core = ProcessCore::spawn_host(&host_path(), &mock_core_path()).expect("the host is not started");
+    let mut vec_red = square();
+    assert!(vec_red.send_model(Arc::new(core)));
+    edit_build_and_query(&mut vec_red);
+    // the core only appends, so the last point comes back
+    delete_and_undo(&mut vec_red, 3);
+    assert!(vec_red.core.as_ref().is_some_and(|core| core.is_alive()));
+
+    // a point before the last one can not be put back into the core, the undo waits for the detached model
+    vec_red.chosen_point = Some((vec_red.model.points(1), vec_red.model.points_r(1), 1));
+    let _ = vec_red.update(Message::DeletePoint);
+    let _ = vec_red.update(Message::Undo);
+    assert_eq!((vec_red.model.points_len(), vec_red.model.prims_len()), (3, 2));
+    vec_red.detach();
+    for _ in 0..3 {
+        let _ = vec_red.update(Message::Undo);
+    }
+    assert_eq!((vec_red.model.points_len(), vec_red.model.prims_len()), (4, 4));
+    assert_eq!(*vec_red.model.prims(0), [0, 1, -1]);
+}
+
+#[test]
+fn own_model_undoes_a_delete() {
+    let mut vec_red = square();
+    let mut point = vec_red.model.tb_point_ref(1).cloned().expect("no point");
+    (point.TypPoint, point.Vp, point.Ip) = (1, 0.5, 2.);
+    vec_red.model.commit_point(1, &point).expect("the point is not edited");
+    let mut prim = vec_red.model.t_primitive_ref(0).cloned().expect("no prim");
+    (prim.TypPrim, prim.Vp, prim.Ip, prim.IsFront) = (2, 0.25, 3., true);
+    vec_red.model.commit_prim(0, &prim).expect("the prim is not edited");
+    delete_and_undo(&mut vec_red, 1);
+}
+
+#[test]
+fn process_core_survives_a_detach_and_a_new_send() {
+    let core: Arc<dyn MathCore> = Arc::new(ProcessCore::spawn_host(&host_path(), &mock_core_path()).expect("the host is not started"));
+    let mut vec_red = square();
+    assert!(vec_red.send_model(core.clone()));
+    vec_red.detach();
+    assert!(!vec_red.model.is_borrowed());
+    assert_eq!(vec_red.model.points_len(), 4);
+    // the core still has the model, but the detached one is not built there and gets no regions
+    let _ = vec_red.create_triangle();
+    assert!(vec_red.run.is_none());
+    vec_red.create_region(Point::new(50., 50.));
+    assert_eq!(vec_red.model.regions_len(), 0);
+    assert_eq!(core.regions_ref().1, 0);
+    assert!(vec_red.send_model(core));
+    assert!(vec_red.model.is_borrowed());
+    assert_eq!(vec_red.model.prims_len(), 4);
+}
+
+#[test]
+fn process_core_runs_a_core_without_optional_symbols() {
+    let core: Arc<dyn MathCore> = Arc::new(ProcessCore::spawn_host(&host_path(), &old_mock_core_path()).expect("the host is not started"));
+    assert!(!core.symbols().contains(&"FGetNReg"));
+    let mut vec_red = square();
+    assert!(vec_red.send_model(core.clone()));
+    vec_red.create_region(Point::new(50., 50.));
+    assert_eq!(vec_red.model.regions_len(), 1);
+
+    // what the host prints about the library does not get into the replies
+    assert!(!core.open_dat(&format!("{}.mke", "long".repeat(100))));
+    assert!(!core.open_dat(&target_dir().join("missing.mke").to_string_lossy()));
+    assert!(core.is_alive());
+    assert_eq!(core.get_point_xy(100., 100.), 2);
+}
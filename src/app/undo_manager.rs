@@ -1,9 +1,14 @@
 use iced::Point;
 use crate::model::Model;
+use crate::model::borrow_types::{TBPoint, TPrimitive};
+use crate::model::entity_ids::{Entity, EntityId};
+
+/// Err if the model can not take the change back, e.g. a borrowed one, then the action is kept.
+type UndoAction = Box<dyn Fn(&mut Model) -> Result<(), String> + Send>;
 
 ///Contains functions to undo actions
 pub struct UndoManager {
-    undo_stack: Vec<Box<dyn FnOnce(&mut Model) + Send>>,
+    undo_stack: Vec<UndoAction>,
     pub max_len: usize,
 }
 
@@ -11,52 +16,70 @@ impl UndoManager {
     pub fn clear(&mut self) {
         self.undo_stack.clear()
     }
-    pub fn deleted_point(&mut self, point: Point, point_r: f32, num: usize) {
-        let func: Box<dyn FnOnce(&mut Model) + Send> = Box::new(move |model: &mut Model| {
-            let len = model.points_len();
-            model.points_push(point, point_r);
-            model.points_swap(len, num);
-            model.replace_prim(num as i32, len as i32)
+    /// The point comes back as it was, with its old `id`.
+    pub fn deleted_point(&mut self, record: TBPoint, num: usize, id: Option<EntityId>) {
+        let func: UndoAction = Box::new(move |model: &mut Model| {
+            model.points_insert_record(num, record.clone())?;
+            if let Some(id) = id {
+                model.set_id(Entity::Point, num, id)
+            }
+            Ok(())
         });
         self.push(func);
     }
-    pub fn deleted_prim(&mut self, placement: usize, line: [i32; 3]) {
-        let func: Box<dyn FnOnce(&mut Model) + Send> = Box::new(move |model: &mut Model| {
-            model.prims_insert(placement, line);
+    /// The prim comes back as it was, with its old `id`.
+    pub fn deleted_prim(&mut self, placement: usize, record: TPrimitive, id: Option<EntityId>) {
+        let func: UndoAction = Box::new(move |model: &mut Model| {
+            model.prims_insert_record(placement, record.clone())?;
+            if let Some(id) = id {
+                model.set_id(Entity::Prim, placement, id)
+            }
+            Ok(())
         });
         self.push(func)
     }
     pub fn pushed_point(&mut self) {
-        let func: Box<dyn FnOnce(&mut Model) + Send> = Box::new(move |model: &mut Model| {
-            model.points_pop();
+        let func: UndoAction = Box::new(move |model: &mut Model| {
+            model.points_pop()
         });
         self.push(func);
     }
     pub fn pushed_prim(&mut self) {
-        let func: Box<dyn FnOnce(&mut Model) + Send> = Box::new(move |model: &mut Model| {
-            model.prims_pop();
+        let func: UndoAction = Box::new(move |model: &mut Model| {
+            model.prims_pop()
         });
         self.push(func);
     }
     pub fn changed_point(&mut self, old: (Point, f32), num: usize) {
-        let func: Box<dyn FnOnce(&mut Model) + Send> = Box::new(move |model: &mut Model| {
-            model.point_set(num, old.0, old.1);
+        let func: UndoAction = Box::new(move |model: &mut Model| {
+            model.point_set(num, old.0, old.1)
         });
         self.undo_stack.push(func);
     }
-    fn push(&mut self, f: Box<dyn FnOnce(&mut Model) + Send>) {
+    /// `old` holds the index, `TypPrim` and `Vp` of every changed prim.
+    pub fn changed_conditions(&mut self, old: Vec<(usize, (u8, f64))>) {
+        let func: UndoAction = Box::new(move |model: &mut Model| {
+            for &(index, (typ, vp)) in &old {
+                model.set_prim_condition(index, typ, vp)?;
+            }
+            Ok(())
+        });
+        self.push(func);
+    }
+    fn push(&mut self, f: UndoAction) {
         self.undo_stack.push(f);
         if self.undo_stack.len() >= self.max_len {
             let _ = self.undo_stack.remove(0);
         }
     }
-    pub fn undo(&mut self) -> Box<dyn FnOnce(&mut Model) + Send> {
-        if let Some(func) = self.undo_stack.pop() {
-            func
-        }
-        else {
-            Box::new(|_|{})
-        }
+    /// Takes back the last action. One the model refuses stays in the journal.
+    pub fn undo(&mut self, model: &mut Model) -> Result<(), String> {
+        let Some(func) = self.undo_stack.last() else {
+            return Ok(())
+        };
+        func(model)?;
+        self.undo_stack.pop();
+        Ok(())
     }
 }
 
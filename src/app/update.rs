@@ -1,14 +1,22 @@
+use iced::{Point, Task};
 use crate::{Message, VecRed};
 use crate::app_config::app_config::Change;
-use crate::foreign_functions::*;
-use crate::model::load_model;
+use crate::app_config::settings::CoreSource;
+use crate::model::{dxf, load_model, painter, png, project, svg};
+use crate::model::entity_ids::Entity;
 use super::core::CallByName;
+use super::update_lib::pick_core;
 
 impl VecRed {
-    pub fn update(&mut self, message: Message) {
+    pub fn update(&mut self, message: Message) -> Task<Message> {
+        if self.run.is_some() && Self::needs_core(&message) {
+            println!("Math core is busy");
+            return Task::none()
+        }
         match message {
             Message::ChangeMode(new_mode) => {
                 self.mode = new_mode;
+                self.chosen_prims.clear();
             }
 
             Message::EditPath(edited) => {
@@ -39,22 +47,22 @@ impl VecRed {
 
             Message::DeletePoint => {
                 let Some((_, _, num)) = self.chosen_point else {
-                    return
+                    return Task::none()
                 };
                 
                 let pred = |x: &[i32; 3]| { x[0] != num as i32 && x[1] != num as i32 && x[2] != num as i32 };
-                self.model.prims_retain_safe(pred, &mut self.journal);
-                if self.model.points_len() >= 1 && num != self.model.points_len() - 1 {
-                    self.journal.deleted_point(self.model.points(num), self.model.points_r(num), num);
-                    self.model.points_swap(num, self.model.points_len() - 1);
-                    self.model.points_pop();
-                }
-                else {
-                    self.journal.deleted_point(self.model.points(num), self.model.points_r(num), num);
-                    self.model.points_pop();
-                }
-                if num != self.model.points_len() {
-                    self.model.replace_prim(self.model.points_len() as i32, num as i32);
+                let deleted = self.model.prims_retain_safe(pred, &mut self.journal).and_then(|_| {
+                    let Some(record) = self.model.tb_point_ref(num).cloned() else {
+                        return Ok(())
+                    };
+                    let id = self.model.id(Entity::Point, num);
+                    self.model.points_remove(num)?;
+                    self.journal.deleted_point(record, num, id);
+                    Ok(())
+                });
+                if let Err(error) = deleted {
+                    self.core_status = error;
+                    println!("{}", self.core_status);
                 }
                 if num < self.model.points_len() {
                     self.chosen_point.as_mut().unwrap().0 = self.model.points(num);
@@ -64,14 +72,25 @@ impl VecRed {
                     self.chosen_point = None;
                 }
                 self.mode = "Move";
+                self.chosen_prims.clear();
                 self.state.redraw();
             }
             
             Message::FindEverything(x, y) => {
-                if let Some(lib) = self.lib.as_ref() {
-                    let prim = f_get_prim_xy(lib.clone(), x, y);
-                    let node = f_get_node_xy(lib.clone(), x, y);
-                    let region = f_get_region_xy(lib.clone(), x, y);
+                if !self.model.is_borrowed() {
+                    let point = Point::new(x as f32, y as f32);
+                    let zoom_scale = self.app_config.zoom.scale;
+                    let found = |index: Option<usize>| index.map_or(-1, |index| index as i32);
+                    self.chosen_elems = Some(CallByName{
+                        prim: found(self.model.find_prim(point, self.scale, zoom_scale)),
+                        node: found(self.model.find_node(point, self.scale, zoom_scale)),
+                        region: found(self.model.find_region(point))
+                    })
+                }
+                else if let Some(core) = self.core.as_ref() {
+                    let prim = core.get_prim_xy(x, y);
+                    let node = core.get_node_xy(x, y);
+                    let region = core.get_region_xy(x, y);
                     
                     self.chosen_elems = Some(CallByName{prim, node, region})
                 }
@@ -89,8 +108,12 @@ impl VecRed {
             }
 
             Message::Undo => {
-                self.journal.undo()(&mut self.model);
+                if let Err(error) = self.journal.undo(&mut self.model) {
+                    self.core_status = format!("{error}, the undo is kept");
+                    println!("{}", self.core_status);
+                }
                 self.mode = "Move";
+                self.chosen_prims.clear();
                 self.state.redraw()
             }
 
@@ -98,6 +121,8 @@ impl VecRed {
                 self.model.clear();
                 self.journal.clear();
                 self.chosen_point = None;
+                self.chosen_prims.clear();
+                self.diagnostics.clear();
                 self.state.redraw()
             }
             
@@ -159,22 +184,93 @@ impl VecRed {
                 self.app_config.update(action)
             }
 
+            Message::ExportModel if svg::is_svg(&self.path_to_load.text()) => {
+                return self.update(Message::ExportSvg(false))
+            }
+
+            Message::ExportModel if png::is_png(&self.path_to_load.text()) => {
+                match png::save_png(&self.path_to_load.text(), &self.model, self.scale, &self.app_config) {
+                    Ok(()) => println!("Done"),
+                    Err(error) => println!("{error}")
+                }
+            }
+
             Message::ExportModel => {
-                if !load_model::export_model(&self.lib, self.path_to_load.text(), &self.model) {
+                let path = self.path_to_load.text();
+                let done = if project::is_project(&path) {
+                    project::save_project(&path, &self.model, &self.app_config)
+                        .map_err(|error| println!("{error}"))
+                        .is_ok()
+                } else if dxf::is_dxf(&path) {
+                    dxf::save_dxf(&path, &self.model)
+                        .map_err(|error| println!("{error}"))
+                        .is_ok()
+                } else {
+                    load_model::export_model(&self.core, path, &self.model)
+                };
+                if !done {
                     println!("Not done!")
                 } else {
                     println!("Done")
                 }
             }
 
+            Message::ExportSvg(whole) => {
+                let path = self.path_to_load.text();
+                let exported = if whole {
+                    let side = self.app_config.model_size.width.max(self.app_config.model_size.height);
+                    let view = painter::whole_model_view(&self.model, &self.app_config, side);
+                    svg::save_svg(&path, &self.model, self.scale, &view)
+                } else {
+                    svg::save_svg(&path, &self.model, self.scale, &self.app_config)
+                };
+                match exported {
+                    Ok(()) => println!("Done"),
+                    Err(error) => println!("{error}")
+                }
+            }
+
             Message::OpenModel => {
-                if load_model::open_model(&self.lib, self.path_to_load.text(), &mut self.model) {
+                let path = self.path_to_load.text();
+                let opened = if project::is_project(&path) {
+                    match project::open_project(&path) {
+                        Ok((model, view)) => {
+                            self.model = model;
+                            self.app_config.apply_view_state(&view);
+                            true
+                        }
+                        Err(error) => {
+                            println!("{error}");
+                            false
+                        }
+                    }
+                } else if dxf::is_dxf(&path) {
+                    match dxf::open_dxf(&path, self.default_circle) {
+                        Ok(model) => {
+                            self.model = model;
+                            let (min, max) = self.model.find_min_max();
+                            let _ = self.update(Message::SetZoom(min, max, true));
+                            true
+                        }
+                        Err(error) => {
+                            println!("{error}");
+                            false
+                        }
+                    }
+                } else if load_model::open_model(&self.core, path, &mut self.model) {
                     let (min, max) = self.model.find_min_max();
-                    self.update(Message::SetZoom(min, max, true));
-
+                    let _ = self.update(Message::SetZoom(min, max, true));
+                    true
+                } else {
+                    false
+                };
+                if opened {
                     self.mode = "Move";
                     self.chosen_point = None;
+                    self.chosen_elems = None;
+                    self.chosen_prims.clear();
                     self.journal.clear();
+                    self.check_model();
                     self.state.redraw();
 
                     println!("Done")
@@ -183,17 +279,128 @@ impl VecRed {
                 }
             }
 
+            Message::EditCorePath(edited) => {
+                self.core_path.perform(edited);
+                self.core_source = Some(CoreSource::Typed)
+            }
+
+            Message::PickCore => {
+                return Task::perform(pick_core(), Message::CorePicked)
+            }
+
+            Message::CorePicked(path) => {
+                if let Some(path) = path {
+                    self.core_path = iced::widget::text_editor::Content::with_text(&path);
+                    self.core_source = Some(CoreSource::Picker)
+                }
+            }
+
             Message::OpenMathCore => {
                 self.open_math_core()
             }
             
+            Message::CoreProcess(separate) => {
+                self.settings.core_process = separate;
+                if !self.settings.save() {
+                    println!("Settings are not saved")
+                }
+            }
+
+            Message::RestartCore => {
+                self.restart_math_core()
+            }
+
             Message::CreateRegion(point) => {
                 self.create_region(point)
             }
 
+            Message::ReloadCore => {
+                self.reload_math_core()
+            }
+
+            Message::Detach => {
+                self.detach()
+            }
+
+            Message::FreeCoreOnDetach(free) => {
+                self.free_core_on_detach = free
+            }
+
+            Message::ZoomRegion(index) => {
+                if let Some((center, radius)) = self.model.region_marker(index) {
+                    let margin = iced::Vector::new(radius.max(1.0) * 2.0, radius.max(1.0) * 2.0);
+                    return self.update(Message::SetZoom(center - margin, center + margin, true))
+                }
+            }
+
+            Message::SelectRegion(index) => {
+                self.chosen_elems = Some(CallByName{prim: -1, node: -1, region: index as i32})
+            }
+
+            Message::Validate => {
+                if self.check_model() {
+                    println!("No problems")
+                }
+            }
+
+            Message::ZoomDiagnostic(index) => {
+                let subject = self.diagnostics.get(index).map(|diagnostic| diagnostic.subject);
+                if let Some(center) = subject.and_then(|subject| self.model.locate(subject)) {
+                    let (min, max) = self.model.find_min_max();
+                    let size = ((max.x - min.x).max(max.y - min.y) * 0.05).max(1.0);
+                    let margin = iced::Vector::new(size, size);
+                    return self.update(Message::SetZoom(center - margin, center + margin, true))
+                }
+            }
+
+            Message::ChooseBoundary(boundary) => {
+                self.boundary = boundary
+            }
+
+            Message::ApplyBoundary => {
+                self.apply_boundary()
+            }
+
+            Message::UnselectPrims => {
+                self.chosen_prims.clear();
+                self.state.redraw()
+            }
+
             Message::CreateTriangle => {
-                self.create_triangle()
+                return self.create_triangle()
+            }
+
+            Message::TriangleBuilt(out) => {
+                self.finish_run(out)
+            }
+
+            Message::CancelRun => {
+                self.cancel_run()
+            }
+
+            Message::RunTick(now) => {
+                if let Some(run) = self.run.as_mut() {
+                    run.elapsed = now - run.started
+                }
+            }
+
+            Message::CheckCore => {
+                if self.core.as_ref().is_some_and(|core| !core.is_alive()) {
+                    self.core_status = String::from("Math core has stopped");
+                    println!("{}", self.core_status);
+                }
             }
         }
+        Task::none()
+    }
+
+    /// Messages that touch the model or the math core and have to wait for a run to finish.
+    fn needs_core(message: &Message) -> bool {
+        matches!(message,
+            Message::DefPoint(..) | Message::DefPrim(..) | Message::DeletePoint |
+            Message::FindEverything(..) | Message::ChangeParams(..) | Message::ChangeApply |
+            Message::Undo | Message::ClearAll | Message::ExportModel | Message::ExportSvg(..) | Message::OpenModel |
+            Message::OpenMathCore | Message::RestartCore | Message::CreateRegion(..) | Message::CreateTriangle |
+            Message::ApplyBoundary | Message::Detach | Message::ReloadCore)
     }
 }
@@ -6,13 +6,19 @@ impl VecRed {
     pub(super) fn def_point(&mut self, point: Point) {
         let number = self.model.find_point(point, self.scale, self.app_config.zoom.scale);
         if self.mode == "Region" && number == self.model.points_len() {
-            self.update(Message::CreateRegion(point))
+            let _ = self.update(Message::CreateRegion(point));
         } else if self.mode == "Find" {
-            self.update(Message::FindEverything(point.x as f64, point.y as f64))
+            let _ = self.update(Message::FindEverything(point.x as f64, point.y as f64));
+        } else if self.mode == "Boundary" {
+            self.select_prim(point)
         } else {
             if number == self.model.points_len() {
+                if let Err(error) = self.model.points_push(point, self.default_circle) {
+                    self.core_status = error;
+                    println!("{}", self.core_status);
+                    return
+                }
                 self.journal.pushed_point();
-                self.model.points_push(point, self.default_circle);
                 self.state.redraw();
             }
             self.chosen_point = Some((self.model.points(number), self.model.points_r(number), number));
@@ -24,30 +30,47 @@ impl VecRed {
         }
     }
     
+    /// Adds the prim under `point` to [VecRed::chosen_prims], or removes it if it is there.
+    #[inline]
+    fn select_prim(&mut self, point: Point) {
+        if let Some(index) = self.model.find_prim(point, self.scale, self.app_config.zoom.scale) {
+            match self.chosen_prims.iter().position(|&chosen| chosen == index) {
+                Some(position) => { self.chosen_prims.remove(position); }
+                None => self.chosen_prims.push(index)
+            }
+            self.state.redraw()
+        }
+    }
+
     #[inline]
     pub(super) fn def_prim(&mut self, points: Vec<Point>, prim: (i32, i32, i32)) {
         let zoom_scale = self.app_config.zoom.scale;
-        let add_point = |vec_red: &mut VecRed, point: Point| {
+        let add_point = |vec_red: &mut VecRed, point: Point| -> Result<usize, String> {
             let number = vec_red.model.find_point(point, vec_red.scale, zoom_scale);
             if number == vec_red.model.points_len() {
+                vec_red.model.points_push(point, vec_red.default_circle)?;
                 vec_red.journal.pushed_point();
-                vec_red.model.points_push(point, vec_red.default_circle);
             }
-            number
+            Ok(number)
         };
-        let a = add_point(self, points[0]);
-        let b = add_point(self, points[1]);
-        if prim.2 == -1 {
-            if a != b {
+        let mut add_prim = || -> Result<(), String> {
+            let a = add_point(self, points[0])?;
+            let b = add_point(self, points[1])?;
+            let added = if prim.2 == -1 {
+                (a != b).then_some([a as i32, b as i32, -1])
+            } else {
+                let c = add_point(self, points[2])?;
+                (a != b && a != c && b != c).then_some([a as i32, b as i32, c as i32])
+            };
+            if let Some(added) = added {
+                self.model.prims_push(added)?;
                 self.journal.pushed_prim();
-                self.model.prims_push([a as i32, b as i32, -1])
-            }
-        } else {
-            let c = add_point(self, points[2]);
-            if a != b && a != c && b != c {
-                self.journal.pushed_prim();
-                self.model.prims_push([a as i32, b as i32, c as i32])
             }
+            Ok(())
+        };
+        if let Err(error) = add_prim() {
+            self.core_status = error;
+            println!("{}", self.core_status);
         }
         self.state.redraw();
         self.chosen_point = None
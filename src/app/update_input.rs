@@ -1,4 +1,8 @@
 use crate::VecRed;
+use crate::math_core::RegionParams;
+
+/// Index of `MatCharName` in the region fields, after the ones of `set_field_by_index`.
+pub(super) const REGION_MAT_CHAR_NAME: usize = 13;
 
 impl VecRed {
     #[inline(always)]
@@ -9,13 +13,70 @@ impl VecRed {
             *chosen_r = self.point_string[2].parse::<f32>().unwrap();
 
             if self.model.points(*chosen_num) != *chosen_p || self.model.points_r(*chosen_num) != *chosen_r {
-                self.journal.changed_point((self.model.points(*chosen_num), self.model.points_r(*chosen_num)), *chosen_num);
-                self.model.point_set(*chosen_num, *chosen_p, *chosen_r);
+                let old = (self.model.points(*chosen_num), self.model.points_r(*chosen_num));
+                match self.model.point_set(*chosen_num, *chosen_p, *chosen_r) {
+                    Ok(()) => self.journal.changed_point(old, *chosen_num),
+                    Err(error) => {
+                        self.core_status = error;
+                        println!("{}", self.core_status);
+                    }
+                }
                 self.state.redraw();
             }
         }
     }
 
+    /// Fills [VecRed::diagnostics], `true` if nothing is wrong.
+    pub(super) fn check_model(&mut self) -> bool {
+        self.diagnostics = self.model.validate();
+        for diagnostic in &self.diagnostics {
+            println!("{diagnostic}")
+        }
+        self.diagnostics.is_empty()
+    }
+
+    /// Stores [VecRed::boundary] with its value in every chosen prim.
+    #[inline(always)]
+    pub(super) fn apply_boundary(&mut self) {
+        if !self.model.editable_physics() {
+            self.core_status = String::from("Physics of a sent model can not be changed, detach it first");
+            println!("{}", self.core_status);
+            return
+        }
+        let vp = match self.boundary_value.trim().parse::<f64>() {
+            Ok(vp) if vp.is_finite() => vp,
+            _ => {
+                self.core_status = format!("Failed to parse '{}' for the boundary value", self.boundary_value);
+                println!("{}", self.core_status);
+                return
+            }
+        };
+        self.chosen_prims.retain(|&index| index < self.model.prims_len());
+        if self.chosen_prims.is_empty() {
+            return
+        }
+        
+        // only the prims that took the condition are undone
+        let mut old = vec![];
+        let mut errors = vec![];
+        for &index in &self.chosen_prims {
+            let condition = self.model.prim_condition(index);
+            match self.model.set_prim_condition(index, self.boundary.byte(), vp) {
+                Ok(()) => old.push((index, condition)),
+                Err(error) => errors.push(error)
+            }
+        }
+        if !old.is_empty() {
+            self.journal.changed_conditions(old);
+        }
+        if !errors.is_empty() {
+            self.core_status = errors.join("; ");
+            println!("{}", self.core_status);
+        }
+        self.chosen_prims.clear();
+        self.state.redraw()
+    }
+
     #[inline(always)]
     fn change_point(&mut self, order: usize) {
         if let Ok(new_value) = self.point_string[order].trim().parse::<f32>() {
@@ -42,18 +103,56 @@ impl VecRed {
             self.change_point(order)
         }
         else {
+            if matches!(what, "point" | "prim" | "region") && !self.model.editable_physics() {
+                self.core_status = String::from("Physics of a sent model can not be changed, detach it first");
+                println!("{}", self.core_status);
+                return
+            }
             match what {
                 "point" => {
-                    let point = self.model.tb_point_ref(index).unwrap();
-                    let _ = point.set_field_by_index(order, &new_value);
+                    if let Some(mut point) = self.model.tb_point_ref(index).cloned() {
+                        let result = point.set_field_by_index(order, &new_value)
+                            .and_then(|_| self.model.commit_point(index, &point));
+                        if let Err(error) = result {
+                            self.core_status = error;
+                            println!("{}", self.core_status)
+                        }
+                    }
                 },
                 "prim" => {
-                    let prim = self.model.t_primitive_ref(index).unwrap();
-                    let _ = prim.set_field_by_index(order, &new_value);
+                    if let Some(mut prim) = self.model.t_primitive_ref(index).cloned() {
+                        let result = prim.set_field_by_index(order, &new_value)
+                            .and_then(|_| self.model.commit_prim(index, &prim));
+                        if let Err(error) = result {
+                            self.core_status = error;
+                            println!("{}", self.core_status)
+                        }
+                    }
                 },
                 "region" => {
-                    let region = self.model.t_region_ref(index).unwrap();
-                    let _ = region.set_field_by_index(order, &new_value);
+                    let Some(mut edited) = self.model.t_region_ref(index).cloned() else {
+                        return
+                    };
+                    let before = RegionParams::from_region(&edited);
+                    let result = if order == REGION_MAT_CHAR_NAME {
+                        edited.set_mat_char_name(new_value.trim());
+                        Ok(())
+                    } else {
+                        edited.set_field_by_index(order, &new_value)
+                    };
+                    let result = result
+                        .and_then(|_| RegionParams::from_region(&edited).validate_changed(&before))
+                        .and_then(|_| self.model.commit_region(index, &edited));
+                    if let Err(error) = result {
+                        self.core_status = error;
+                        println!("{}", self.core_status)
+                    }
+                }
+                "new_region" => {
+                    self.region_string[order] = new_value;
+                }
+                "boundary" => {
+                    self.boundary_value = new_value;
                 }
                 _ => panic!("No such thing to change!")
             }
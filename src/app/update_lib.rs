@@ -1,7 +1,10 @@
-use iced::Point;
-use libloading::Library;
-use crate::foreign_functions::*;
-use crate::VecRed;
+use iced::{Point, Task};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use iced::futures::channel::oneshot;
+use crate::math_core::{LibCore, MathCore, ProcessCore, RegionParams};
+use crate::{Message, VecRed};
+use super::core::CoreRun;
 
 use crate::model::borrow_types::*;
 
@@ -44,63 +47,303 @@ macro_rules! impl_field_setter {
     (@step $self:ident, $idx:ident, $val:ident, $counter:ident $(,)?) => {};
 }
 
+impl_field_setter![TBPoint, x: f64, y: f64, r: f64, TypPoint: u8, Vp: f64, Ip: f64, NNode: i32, ];
+impl_field_setter![TPrimitive, p: [i32; 3], TypPrim: u8, IsFront: bool, Vp: f64, Ip: f64, ];
+impl_field_setter![TRegion, x: f64, y: f64, R: f64, TriW: bool, CNu: f64, PrMag: u8, Px: f64, Py: f64, W: f64, Ip: f64,
+    ST: f64, PLOT: f64, JbyIW: bool, ];
+
 impl VecRed {
     #[inline(always)]
     pub(super) fn open_math_core(&mut self) {
-        if self.lib.is_some() {
+        if self.core.is_some() {
             println!("Already opened!");
             return;
         }
-        self.lib = unsafe {
-            let temp_lib = Library::new("/home/alexe//Documents/FLib.dll");
-            if temp_lib.is_err() {
-                println!("No library");
+        let path = self.core_path.text().trim().to_string();
+        if path.is_empty() {
+            self.core_status = String::from("No path to the math core");
+            println!("{}", self.core_status);
+            return;
+        }
+        let core = match self.load_math_core(&path) {
+            Ok(core) => core,
+            Err(error) => {
+                self.core_status = error;
+                println!("{}", self.core_status);
                 return;
             }
-            Some(
-                std::rc::Rc::new(
-                    temp_lib.unwrap()
-                ))
         };
-        let lib = self.lib.as_ref().unwrap();
-        f_init_model(lib.clone());
+        self.core_status = String::from("Math core is opened");
+        if self.settings.core_path.as_ref() != Some(&path) {
+            self.settings.core_path = Some(path);
+            if !self.settings.save() {
+                println!("Settings are not saved")
+            }
+        }
+        self.send_model(core);
+    }
+
+    /// Opens the library in this process or in a core host, depending on the settings.
+    fn load_math_core(&self, path: &str) -> Result<Arc<dyn MathCore>, String> {
+        if self.settings.core_process {
+            Ok(Arc::new(ProcessCore::spawn(path)?))
+        } else {
+            Ok(Arc::new(LibCore::open(path)?))
+        }
+    }
+
+    /// Creates every point and prim of the current model in `core` and borrows the model from it.
+    /// A model with problems is not sent, they are listed in [VecRed::diagnostics] instead.
+    pub(super) fn send_model(&mut self, core: Arc<dyn MathCore>) -> bool {
+        if !self.check_model() {
+            self.core_status = format!("The model has {} problems, it is not sent", self.diagnostics.len());
+            println!("{}", self.core_status);
+            return false
+        }
+        let regions: Vec<TRegion> = (0..self.model.regions_len())
+            .filter_map(|i| self.model.t_region_ref(i).cloned())
+            .collect();
+        
+        // the create calls take no TypPoint, Vp and Ip of a point, no IsFront and Ip of a prim
+        let (point, prim) = (TBPoint::default(), TPrimitive::default());
+        let not_sent = (0..self.model.points_len())
+            .filter_map(|i| self.model.tb_point_ref(i))
+            .filter(|record| (record.TypPoint, record.Vp, record.Ip) != (point.TypPoint, point.Vp, point.Ip))
+            .count()
+            + (0..self.model.prims_len())
+            .filter_map(|j| self.model.t_primitive_ref(j))
+            .filter(|record| (record.IsFront, record.Ip) != (prim.IsFront, prim.Ip))
+            .count();
+
+        core.init_model();
         for i in 0..self.model.points_len() {
-            f_create_point(lib.clone(), (self.model.points(i), self.model.points_r(i)));
+            core.create_point(self.model.points(i), self.model.points_r(i));
         }
         for j in 0..self.model.prims_len() {
-            f_create_prim(lib.clone(), self.model.prims(j));
+            let (typ_prim, vp) = self.model.prim_condition(j);
+            core.create_prim(self.model.prims(j), typ_prim, vp);
+        }
+        for region in &regions {
+            let point = Point::new(region.x as f32, region.y as f32);
+            core.create_region(&point, &RegionParams::from_region(region));
+        }
+        if not_sent > 0 {
+            self.core_status = format!("{not_sent} points and prims lose TypPoint, Vp, Ip or IsFront, the core has no call for them");
+            println!("{}", self.core_status)
         }
 
-        let points_ref = get_points_ref(lib.clone());
-        let prims_ref = get_prims_ref(lib.clone());
-        let nodes_ref = get_nodes_ref(lib.clone());
-        let elems_ref = get_elems_ref(lib.clone());
-        let regions_ref = get_regions_ref(lib.clone());
-        let library = self.lib.as_ref().unwrap().clone();
-        self.model.make_borrow(library, points_ref, prims_ref, nodes_ref, elems_ref, regions_ref);
-        
-        impl_field_setter![TBPoint, x: f64, y: f64, r: f64, TypPoint: u8, Vp: f64, Ip: f64, NNode: i32, ];
-        impl_field_setter![TPrimitive, p: [i32; 3], TypPrim: u8, IsFront: bool, Vp: f64, Ip: f64, ];
-        impl_field_setter![TRegion, TriW: bool, CNu: f64, PrMag: u8, Px: f64, Py: f64, W: f64, Ip: f64, ];
+        let points_ref = core.points_ref();
+        let prims_ref = core.prims_ref();
+        let nodes_ref = core.nodes_ref();
+        let elems_ref = core.elems_ref();
+        let regions_ref = core.regions_ref();
+        self.core = Some(core.clone());
+        self.model.make_borrow(core, points_ref, prims_ref, nodes_ref, elems_ref, regions_ref);
+
         println!("Finished");
         self.state.redraw();
+        true
     }
-    
+
+    /// Starts a dead core again and sends it the geometry it had.
+    #[inline(always)]
+    pub(super) fn restart_math_core(&mut self) {
+        let path = self.core_path.text().trim().to_string();
+        match self.load_math_core(&path) {
+            Ok(core) => {
+                self.chosen_elems = None;
+                if self.send_model(core) {
+                    self.core_status = String::from("Math core is restarted");
+                }
+            }
+            Err(error) => self.core_status = error
+        }
+        println!("{}", self.core_status);
+    }
+
+    /// Loads the library from the path again, or another one, without restarting the editor.
+    /// The geometry and the regions are copied out first, then the old library is unloaded
+    /// and everything is created again in the new one. The mesh has to be built again.
+    #[inline(always)]
+    pub(super) fn reload_math_core(&mut self) {
+        let path = self.core_path.text().trim().to_string();
+        if path.is_empty() {
+            self.core_status = String::from("No path to the math core");
+            println!("{}", self.core_status);
+            return
+        }
+        let old_symbols = self.core.as_ref()
+            .filter(|core| core.is_alive())
+            .map(|core| core.symbols());
+
+        self.model = self.model.detach();
+        self.core = None;
+        self.chosen_elems = None;
+        self.chosen_prims.clear();
+
+        let core = match self.load_math_core(&path) {
+            Ok(core) => core,
+            Err(error) => {
+                self.core_status = error;
+                println!("{}", self.core_status);
+                self.state.redraw();
+                return
+            }
+        };
+        let new_symbols = core.symbols();
+        if !self.send_model(core) {
+            return
+        }
+
+        self.core_status = match old_symbols {
+            Some(old_symbols) => format!("Math core is reloaded, {}", symbol_diff(&old_symbols, &new_symbols)),
+            None => String::from("Math core is reloaded")
+        };
+        println!("{}", self.core_status);
+        if self.settings.core_path.as_ref() != Some(&path) {
+            self.settings.core_path = Some(path);
+            if !self.settings.save() {
+                println!("Settings are not saved")
+            }
+        }
+    }
+
+    /// Creates a region with the parameters of the region form, once they pass validation.
+    /// Only a sent model gets it, a detached one would not see the region of the core.
     #[inline(always)]
     pub(super) fn create_region(&mut self, point: Point) {
-        if let Some(lib) = self.lib.as_ref() {
-            let out = f_create_region(lib.clone(), &point);
-            println!("Region is {out}");
+        let Some(core) = self.core.as_ref() else {
+            return
+        };
+        if !self.model.is_borrowed() {
+            self.core_status = String::from("Send the model before creating regions");
+            println!("{}", self.core_status);
+            return
         }
+        let params = match RegionParams::parse(&self.region_string) {
+            Ok(params) => params,
+            Err(error) => {
+                self.core_status = error;
+                println!("{}", self.core_status);
+                return
+            }
+        };
+        let out = core.create_region(&point, &params);
+        self.model.sync_everything();
+        self.core_status = format!("Region is {out}");
+        println!("{}", self.core_status);
+        if out >= 0 && self.settings.region != params {
+            self.settings.region = params;
+            if !self.settings.save() {
+                println!("Settings are not saved")
+            }
+        }
+        self.state.redraw()
     }
     
+    /// Starts `FBuildFM` on a worker thread. The canvas shows a snapshot of the model until
+    /// [Message::TriangleBuilt] comes back. A detached model has to be sent again first.
+    #[inline(always)]
+    pub(super) fn create_triangle(&mut self) -> Task<Message> {
+        let Some(core) = self.core.clone() else {
+            return Task::none()
+        };
+        if !self.model.is_borrowed() {
+            self.core_status = String::from("Send the model before building");
+            println!("{}", self.core_status);
+            return Task::none()
+        }
+        let snapshot = self.model.detach();
+        let model = std::mem::replace(&mut self.model, snapshot);
+        self.run = Some(CoreRun { model, started: Instant::now(), elapsed: Duration::ZERO, cancelled: false });
+        self.core_status = String::from("Building mesh");
+        
+        Task::perform(build_fm(core), Message::TriangleBuilt)
+    }
+
+    /// Puts the borrowed model back and takes the new nodes and elements from the core at once.
+    #[inline(always)]
+    pub(super) fn finish_run(&mut self, out: bool) {
+        let Some(run) = self.run.take() else {
+            return
+        };
+        self.model = run.model;
+        self.model.sync_everything();
+        self.chosen_elems = None;
+        
+        if run.cancelled {
+            self.core_status = String::from("Mesh build is cancelled");
+            println!("{}", self.core_status);
+            self.restart_math_core();
+        } else {
+            self.core_status = format!("Triangle is {out}, {:.1} s", run.elapsed.as_secs_f32());
+            println!("{}", self.core_status);
+        }
+        self.state.redraw()
+    }
+
+    /// Replaces the borrowed model with its own copy, so the core can be unloaded or replaced.
     #[inline(always)]
-    pub(super) fn create_triangle(&mut self) {
-        if let Some(lib) = self.lib.as_ref() {
-            let out = f_build_fm(lib.clone());
-            println!("Triangle is {}", out);
-            self.model.sync_everything();
-            self.state.redraw()
+    pub(super) fn detach(&mut self) {
+        if !self.model.is_borrowed() {
+            return
+        }
+        self.model = self.model.detach();
+        if let Some(core) = self.core.as_ref().filter(|_| self.free_core_on_detach) {
+            core.del_memo_model()
         }
+        self.core_status = String::from("Model is detached");
+        println!("{}", self.core_status);
+        self.state.redraw()
     }
+
+    #[inline(always)]
+    pub(super) fn cancel_run(&mut self) {
+        let (Some(run), Some(core)) = (self.run.as_mut(), self.core.as_ref()) else {
+            return
+        };
+        if core.cancel() {
+            run.cancelled = true;
+            self.core_status = String::from("Cancelling");
+        } else {
+            self.core_status = String::from("The core in this process can not be interrupted, use a separate process");
+        }
+        println!("{}", self.core_status);
+    }
+}
+
+/// Symbols the new library exports and the old one did not, and the other way round.
+fn symbol_diff(old: &[&str], new: &[&str]) -> String {
+    let added: Vec<&str> = new.iter().copied().filter(|name| !old.contains(name)).collect();
+    let removed: Vec<&str> = old.iter().copied().filter(|name| !new.contains(name)).collect();
+    if added.is_empty() && removed.is_empty() {
+        return String::from("same symbols")
+    }
+    let mut diff = vec![];
+    if !added.is_empty() {
+        diff.push(format!("new symbols: {}", added.join(", ")))
+    }
+    if !removed.is_empty() {
+        diff.push(format!("missing symbols: {}", removed.join(", ")))
+    }
+    diff.join("; ")
+}
+
+/// Asks for the math core library in the file dialog of the system.
+pub(super) async fn pick_core() -> Option<String> {
+    rfd::AsyncFileDialog::new()
+        .set_title("Math core")
+        .add_filter("Library", &["dll", "so", "dylib"])
+        .pick_file()
+        .await
+        .map(|file| file.path().to_string_lossy().into_owned())
+}
+
+/// Runs [MathCore::build_fm] on its own thread, so the event loop keeps going.
+async fn build_fm(core: Arc<dyn MathCore>) -> bool {
+    let (sender, receiver) = oneshot::channel();
+    std::thread::spawn(move || {
+        let _ = sender.send(core.build_fm());
+    });
+    receiver.await.unwrap_or(false)
 }
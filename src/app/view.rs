@@ -1,7 +1,8 @@
 use iced::{Center, Fill};
-use iced::widget::{container, row, stack, Scrollable, button, text, text_editor, Column, column, Slider, text_input};
+use iced::widget::{container, row, stack, Scrollable, button, checkbox, text, text_editor, Column, column, Slider, text_input};
 use crate::{Message, VecRed};
 use crate::model::framework::Framework;
+use crate::model::entity_ids::Entity;
 use std::default::Default;
 
 impl VecRed {
@@ -16,7 +17,10 @@ impl VecRed {
                     model: &self.model,
                     scale: self.scale,
                     app_config: &self.app_config,
-                    mode: &self.mode
+                    mode: self.mode,
+                    chosen_prims: &self.chosen_prims,
+                    chosen_region: self.chosen_elems.as_ref()
+                        .and_then(|chosen| usize::try_from(chosen.region).ok())
                 })
                     .width(Fill)
                     .height(Fill)
@@ -70,6 +74,13 @@ impl VecRed {
             point_info = point_info.push(make_separator())
         }
         
+        let mut region_form = Column::new();
+        if self.mode == "Region" {
+            region_form = self.new_region().push(make_separator())
+        } else if self.mode == "Boundary" {
+            region_form = self.boundary_form().push(make_separator())
+        }
+        
         let mut full_info = Column::new();
         if let Some(all_indexes) = self.chosen_elems.as_ref() {
             let prim = self.full_prim(all_indexes.prim);
@@ -87,20 +98,36 @@ impl VecRed {
         let for_path = text_editor(&self.path_to_load).on_action(Message::EditPath).placeholder("/path/to/model");
         let open_model = button("Open model").on_press(Message::OpenModel);
         let export_model = button("Export model").on_press(Message::ExportModel);
+        let export_svg = row![
+            button("SVG of view").on_press(Message::ExportSvg(false)),
+            button("SVG of model").on_press(Message::ExportSvg(true))
+        ].spacing(5);
+        let check_model = button("Check model").on_press(Message::Validate);
+        let mut diagnostics = Column::new();
+        if !self.diagnostics.is_empty() {
+            diagnostics = self.diagnostic_list()
+        }
         let sep_4 = make_separator();
         
         let foreign_functions = self.foreign_functions();
         let sep_5 = make_separator();
         
+        let mut regions = Column::new();
+        if self.model.regions_len() > 0 {
+            regions = self.region_list().push(make_separator())
+        }
+        
         let settings = button("Settings").on_press(Message::ConfigOpen(true));
         
         let full_panel = column!(mode, sep_1, 
             text("Change scale"), change_scale, text("Change default circle"), change_circle, sep_2, 
             point_info, 
+            region_form,
             full_info,
             undo_button, shrink, clear_all, sep_3, 
-            for_path, open_model, export_model, sep_4, 
+            for_path, open_model, export_model, export_svg, check_model, diagnostics, sep_4, 
             foreign_functions, sep_5, 
+            regions,
             settings).spacing(5).align_x(Center);
         
         Scrollable::new(full_panel)
@@ -108,7 +135,7 @@ impl VecRed {
 
     /// Part of the panel about selected [Point].
     fn about_point(&self, num: usize) -> Column<'_, Message> {
-        let point_number = text(format!("Number of point: {}", num));
+        let point_number = text(format!("Number of point: {}{}", num, self.id_suffix(Entity::Point, num)));
         
         let input = |order: usize| { 
             text_input("", &self.point_string[order]).on_input(move |text| Message::ChangeParams("point", num, text, order)) 
@@ -132,10 +159,30 @@ impl VecRed {
     
     /// Part of the panel calling foreign functions
     fn foreign_functions(&self) -> Column<'_, Message> {
+        let source = match self.core_source {
+            Some(source) => format!("Math core ({})", source.as_str()),
+            None => String::from("Math core")
+        };
+        let core_path = text_editor(&self.core_path).on_action(Message::EditCorePath).placeholder("/path/to/FLib.dll");
+        let browse = button("Browse").on_press(Message::PickCore);
+        let separate = checkbox("Separate process", self.settings.core_process).on_toggle(Message::CoreProcess);
         let send_model = button("Send model").on_press(Message::OpenMathCore);
-        let triangle = button("Create triangle").on_press(Message::CreateTriangle);
-        
-        column![send_model, triangle].align_x(Center).spacing(5)
+        let reload = button("Reload core").on_press_maybe(self.core.is_some().then_some(Message::ReloadCore));
+        let mut column = column![text(source), row![core_path, browse].spacing(5).align_y(Center), separate, row![send_model, reload].spacing(10), text(&self.core_status)];
+        if let Some(run) = self.run.as_ref() {
+            column = column.push(text(format!("Busy: {:.1} s", run.elapsed.as_secs_f32())));
+            column = column.push(button("Cancel").on_press(Message::CancelRun));
+        } else {
+            column = column.push(button("Create triangle").on_press_maybe(self.model.is_borrowed().then_some(Message::CreateTriangle)));
+            let detach = button("Detach").on_press_maybe(self.model.is_borrowed().then_some(Message::Detach));
+            let free = checkbox("Free core memory", self.free_core_on_detach).on_toggle(Message::FreeCoreOnDetach);
+            column = column.push(row![detach, free].spacing(10).align_y(Center));
+        }
+        if self.core.as_ref().is_some_and(|core| !core.is_alive()) {
+            column = column.push(text("Math core has stopped"));
+            column = column.push(button("Restart core").on_press(Message::RestartCore));
+        }
+        column.align_x(Center).spacing(5)
     }
     
     /// Changes [Zoom] so that every Point from [Model] fits inside
@@ -1,29 +1,63 @@
-use iced::widget::{row, Row, text, text_input, Column};
+use iced::widget::{button, checkbox, pick_list, row, Row, text, text_input, Column};
 use crate::app::core::VecRed;
+use crate::app::update_input::REGION_MAT_CHAR_NAME;
+use crate::math_core::region::REGION_FIELDS;
+use crate::math_core::RegionParams;
+use crate::model::boundary::Boundary;
+use crate::model::entity_ids::Entity;
+use crate::model::model_impl::region_color_of;
+use crate::model::kinds::{MagnetType, NodeType, PointType};
 use crate::Message;
 
-/// Creates [TextInput] for entering values from keyboard.
+/// Creates [TextInput] for entering values from keyboard, read-only unless `editable`.
 #[inline(always)]
-fn create_text_input(value: String, what: &'static str, num: usize, order: usize, descr: &'static str) -> Row<'static, Message> {
+fn create_text_input(value: String, what: &'static str, num: usize, order: usize, descr: &'static str, editable: bool) -> Row<'static, Message> {
     let mut result = row![];
     result = result.push(text(descr)); 
-    result = result.push(text_input(&value, &value).on_input(move |text| Message::ChangeParams(what, num, text, order)));
+    let on_input = editable.then_some(move |text| Message::ChangeParams(what, num, text, order));
+    result = result.push(text_input(&value, &value).on_input_maybe(on_input));
     result
 }
 
+/// Creates [PickList] for a byte field, the chosen byte goes the same way as typed text.
+/// The byte can also be typed, for codes without a variant. Without `editable` only the value is shown.
+#[inline(always)]
+fn create_pick_list<T>(options: &'static [T], value: T, what: &'static str, num: usize, order: usize, descr: &'static str, editable: bool) -> Row<'static, Message>
+where
+    T: ToString + PartialEq + Clone + 'static,
+    T: Into<u8>
+{
+    if !editable {
+        return row![text(format!("{descr} {}", value.to_string()))]
+    }
+    let byte = value.clone().into().to_string();
+    let input = text_input(&byte, &byte).on_input(move |text| Message::ChangeParams(what, num, text, order));
+    let mut result = row![text(descr)];
+    if !options.is_empty() {
+        result = result.push(pick_list(options, Some(value), move |kind: T| Message::ChangeParams(what, num, kind.into().to_string(), order)));
+    }
+    result.push(input)
+}
+
 impl VecRed {
+    /// ", id N" for the header of the record, empty if the model has no ID for it.
+    pub(super) fn id_suffix(&self, entity: Entity, index: usize) -> String {
+        self.model.id(entity, index).map_or(String::new(), |id| format!(", id {id}"))
+    }
+
     /// Borrowed point's fields.
     pub(super) fn full_point(&self, num: usize) -> Column<'_, Message> {
         let mut column = iced::widget::column![];
+        let editable = self.model.editable_physics();
         if let Some(point) = self.model.tb_point_ref(num) {
-            let typ_point = point.TypPoint.to_string();
-            let typ_point = create_text_input(typ_point, "point", num, 3, "TypPoint");
+            let typ_point = PointType::from_byte(point.TypPoint);
+            let typ_point = create_pick_list(PointType::ALL, typ_point, "point", num, 3, "TypPoint", editable);
 
             let vp = point.Vp.to_string();
-            let vp = create_text_input(vp, "point", num, 4, "Vp");
+            let vp = create_text_input(vp, "point", num, 4, "Vp", editable);
 
             let ip = point.Ip.to_string();
-            let ip = create_text_input(ip, "point", num, 5, "Ip");
+            let ip = create_text_input(ip, "point", num, 5, "Ip", editable);
 
             let n_node = point.NNode.to_string();
             let n_node = text(format!("NNode {}", n_node));
@@ -40,29 +74,30 @@ impl VecRed {
             return column
         }
         let index = index as usize;
+        let editable = self.model.editable_physics();
         if let Some(prim) = self.model.t_primitive_ref(index) {
-            let intro = text(format!("Prim number: {}", index));
+            let intro = text(format!("Prim number: {}{}", index, self.id_suffix(Entity::Prim, index)));
 
             let p1 = prim.p[0].to_string();
-            let p1 = create_text_input(p1, "prim", index, 0, "p1");
+            let p1 = create_text_input(p1, "prim", index, 0, "p1", editable);
             
             let p2 = prim.p[1].to_string();
-            let p2 = create_text_input(p2, "prim", index, 1, "p2");
+            let p2 = create_text_input(p2, "prim", index, 1, "p2", editable);
             
             let p3 = prim.p[2].to_string();
-            let p3 = create_text_input(p3, "prim", index, 2, "p3");
+            let p3 = create_text_input(p3, "prim", index, 2, "p3", editable);
             
-            let typ_prim = prim.TypPrim.to_string();
-            let typ_prim = create_text_input(typ_prim, "prim", index, 3, "TypPrim");
+            let typ_prim = Boundary::from_byte(prim.TypPrim);
+            let typ_prim = create_pick_list(Boundary::ALL, typ_prim, "prim", index, 3, "TypPrim", editable);
             
             let is_front = prim.IsFront.to_string();
-            let is_front = create_text_input(is_front, "prim", index, 4, "IsFront");
+            let is_front = create_text_input(is_front, "prim", index, 4, "IsFront", editable);
             
             let vp = prim.Vp.to_string();
-            let vp = create_text_input(vp, "prim", index, 5, "Vp");
+            let vp = create_text_input(vp, "prim", index, 5, "Vp", editable);
             
             let ip = prim.Ip.to_string();
-            let ip = create_text_input(ip, "prim", index, 6, "Ip");
+            let ip = create_text_input(ip, "prim", index, 6, "Ip", editable);
             
             column = iced::widget::column![intro, p1, p2, p3, typ_prim, is_front, vp, ip];
         }
@@ -80,8 +115,12 @@ impl VecRed {
             
             let x = text(format!("x: {}", node.x));
             let y = text(format!("y: {}", node.y));
+            let typ_node = text(format!("TypNode: {}", NodeType::from_byte(node.TypNode)));
             
-            column = iced::widget::column![intro, x, y];
+            column = iced::widget::column![intro, x, y, typ_node];
+            for (region, angle) in self.model.node_grans(index) {
+                column = column.push(text(format!("Boundary of region {region}, UgUzl: {angle:.4} ({:.1}°)", angle.to_degrees())));
+            }
         }
         column
     }
@@ -92,19 +131,112 @@ impl VecRed {
             return column
         }
         let index = index as usize;
+        let editable = self.model.editable_physics();
         if let Some(region) = self.model.t_region_ref(index) {
-            let intro = text(format!("Region number: {}", index));
-            
-            let triw = region.TriW.to_string();
-            let triw = create_text_input(triw, "region", index, 0, "TriW");
+            let intro = text(format!("Region number: {}{}", index, self.id_suffix(Entity::Region, index)));
+            column = column.push(intro);
             
-            let cnu = region.CNu.to_string();
-            let cnu = create_text_input(cnu, "region", index,  1, "CNu");
+            let fields = [
+                ("x", region.x.to_string()), ("y", region.y.to_string()), ("R", region.R.to_string()),
+                ("TriW", region.TriW.to_string()), ("CNu", region.CNu.to_string()), ("PrMag", region.PrMag.to_string()),
+                ("Px", region.Px.to_string()), ("Py", region.Py.to_string()), ("W", region.W.to_string()),
+                ("Ip", region.Ip.to_string()), ("ST", region.ST.to_string()), ("PLOT", region.PLOT.to_string()),
+                ("JbyIW", region.JbyIW.to_string())
+            ];
+            for (order, (descr, value)) in fields.into_iter().enumerate() {
+                if descr == "PrMag" {
+                    let pr_mag = MagnetType::from_byte(region.PrMag);
+                    column = column.push(create_pick_list(MagnetType::ALL, pr_mag, "region", index, order, descr, editable));
+                } else {
+                    column = column.push(create_text_input(value, "region", index, order, descr, editable));
+                }
+            }
             
-            column = iced::widget::column![intro, triw, cnu];
+            let name = region.mat_char_name();
+            column = column.push(create_text_input(name, "region", index, REGION_MAT_CHAR_NAME, "MatCharName", editable));
+            column = column.push(text(format!("KGran: {}", region.KGran)));
         }
         column
     }
+
+    /// Every region of the model with buttons to zoom to it and to open it in the inspector.
+    pub(super) fn region_list(&self) -> Column<'_, Message> {
+        let mut column = iced::widget::column![text(format!("Regions: {}", self.model.regions_len()))];
+        for index in 0..self.model.regions_len() {
+            let Some(region) = self.model.t_region_ref(index) else {
+                continue
+            };
+            let mut position = text(format!("{index}{}: ({:.1}, {:.1}), R {}",
+                self.id_suffix(Entity::Region, index), region.x, region.y, region.R));
+            if self.app_config.gran_show {
+                position = position.color(region_color_of(index))
+            }
+            let material = text(format!("{}, PrMag {}", region.mat_char_name(), MagnetType::from_byte(region.PrMag)));
+            let current = text(format!("Ip {}, W {}", region.Ip, region.W));
+            let magnetization = text(format!("P ({}, {})", region.Px, region.Py));
+            let buttons = row![
+                button("Go").on_press(Message::ZoomRegion(index)),
+                button("Select").on_press(Message::SelectRegion(index))
+            ].spacing(5);
+            column = column.push(iced::widget::column![position, material, current, magnetization, buttons]);
+        }
+        column.spacing(5)
+    }
+
+    /// Problems of the last check, each with a button to zoom to it.
+    pub(super) fn diagnostic_list(&self) -> Column<'_, Message> {
+        const SHOWN: usize = 100;
+        let mut column = iced::widget::column![text(format!("Problems: {}", self.diagnostics.len()))];
+        for (index, diagnostic) in self.diagnostics.iter().enumerate().take(SHOWN) {
+            let go = button("Go").on_press_maybe(self.model.locate(diagnostic.subject).map(|_| Message::ZoomDiagnostic(index)));
+            column = column.push(row![text(diagnostic.to_string()).width(iced::Fill), go].spacing(5));
+        }
+        if self.diagnostics.len() > SHOWN {
+            column = column.push(text(format!("and {} more", self.diagnostics.len() - SHOWN)));
+        }
+        column.spacing(5)
+    }
+
+    /// Boundary condition for the prims picked in "Boundary" mode.
+    pub(super) fn boundary_form(&self) -> Column<'_, Message> {
+        let mut column = iced::widget::column![text(format!("Chosen prims: {}", self.chosen_prims.len()))];
+        column = column.push(pick_list(Boundary::ALL, Some(self.boundary), Message::ChooseBoundary));
+        let input = text_input("", &self.boundary_value)
+            .on_input(|text| Message::ChangeParams("boundary", 0, text, 0));
+        column = column.push(row![text("Value: "), input]);
+        column = column.push(button("Apply").on_press_maybe(self.model.editable_physics().then_some(Message::ApplyBoundary)));
+        column = column.push(button("Unselect").on_press(Message::UnselectPrims));
+        for boundary in Boundary::ALL {
+            if let Some(color) = boundary.color() {
+                column = column.push(text(boundary.to_string()).color(color));
+            }
+        }
+        column.spacing(5)
+    }
+
+    /// Parameters for the regions created in "Region" mode.
+    pub(super) fn new_region(&self) -> Column<'_, Message> {
+        let mut column = iced::widget::column![text("New region")];
+        for (order, descr) in REGION_FIELDS.into_iter().enumerate() {
+            let value = &self.region_string[order];
+            if matches!(descr, "TriW" | "JbyIW") {
+                let flag = checkbox(descr, value == "true")
+                    .on_toggle(move |flag| Message::ChangeParams("new_region", 0, flag.to_string(), order));
+                column = column.push(flag);
+            } else if descr == "PrMag" {
+                let pr_mag = MagnetType::from_byte(value.parse().unwrap_or_default());
+                column = column.push(create_pick_list(MagnetType::ALL, pr_mag, "new_region", 0, order, descr, true));
+            } else {
+                let input = text_input("", value)
+                    .on_input(move |text| Message::ChangeParams("new_region", 0, text, order));
+                column = column.push(row![text(descr), input]);
+            }
+        }
+        if let Err(error) = RegionParams::parse(&self.region_string) {
+            column = column.push(text(error));
+        }
+        column.spacing(5)
+    }
 }
 
 /*
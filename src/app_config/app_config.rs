@@ -33,7 +33,9 @@ pub enum Change {
     Circles(bool),
     Points(bool),
     Prims(bool),
-    ChangeColor(&'static str, usize, u8),
+    Regions(bool),
+    Gran(bool),
+    Color(&'static str, usize, u8),
     Bound(bool),
     NodePointsShow(bool),
     NodeLineMode(String),
@@ -54,12 +56,16 @@ pub struct AppConfig {
     pub points_show: bool,
     pub prims_show: bool,
     pub circles_show: bool,
+    pub regions_show: bool,
+    /// Outlines of the regions from `TRegion::Gran`, each in its own color.
+    pub gran_show: bool,
     pub node_points_show: bool,
     pub node_mode: NodeMode,
 
     circle_color: [u8; 3],
     point_color: [u8; 3],
     prim_color: [u8; 3],
+    region_color: [u8; 3],
     node_point_color: [u8; 3],
     node_line_color: [u8; 3],
 
@@ -101,6 +107,8 @@ impl AppConfig {
             Change::Circles(new) => self.circles_show = new,
             Change::Points(new) => self.points_show = new,
             Change::Prims(new) => self.prims_show = new,
+            Change::Regions(new) => self.regions_show = new,
+            Change::Gran(new) => self.gran_show = new,
             Change::NodePointsShow(new) => self.node_points_show = new,
             
             Change::NodeLineMode(new) => {
@@ -110,11 +118,12 @@ impl AppConfig {
                     _ => NodeMode::None {}
                 }
             }
-            Change::ChangeColor(name, num, new) => {
+            Change::Color(name, num, new) => {
                 match name {
                     "Circles" => self.circle_color[num] = new,
                     "Points" => self.point_color[num] = new,
                     "Prims" => self.prim_color[num] = new,
+                    "Regions" => self.region_color[num] = new,
                     "Node points" => self.node_point_color[num] = new,
                     //"Node prims" => self.node_line_color[num] = new,
                     _ => println!("No such color for {}", name)
@@ -145,13 +154,15 @@ impl AppConfig {
         let circles = self.color_element("Circles");
         let points = self.color_element("Points");
         let prims = self.color_element("Prims");
+        let regions = self.color_element("Regions");
+        let gran = checkbox("Region boundaries", self.gran_show).on_toggle(|a| ConfigEdit(Change::Gran(a)));
         let node_point = self.color_element("Node points");
         
         let node_line = pick_list(self.node_mode.options(), Some(self.node_mode.as_str()), |a| ConfigEdit(Change::NodeLineMode(a.to_string())));
         
         let bound_grid = checkbox("Bound to grid", self.bound).on_toggle(|a| ConfigEdit(Change::Bound(a)));
         
-        column![circles, points, prims, node_point, node_line, bound_grid, ]
+        column![circles, points, prims, regions, gran, node_point, node_line, bound_grid, ]
     }
 
     fn color_element(&self, name: &'static str) -> container::Container<'_, Message> {
@@ -159,12 +170,13 @@ impl AppConfig {
             "Circles" => (checkbox(name, self.circles_show).on_toggle(|a| ConfigEdit(Change::Circles(a))), self.circle_color),
             "Points" => (checkbox(name, self.points_show).on_toggle(|a| ConfigEdit(Change::Points(a))), self.point_color),
             "Prims" => (checkbox(name, self.prims_show).on_toggle(|a| ConfigEdit(Change::Prims(a))), self.prim_color),
+            "Regions" => (checkbox(name, self.regions_show).on_toggle(|a| ConfigEdit(Change::Regions(a))), self.region_color),
             "Node points" => (checkbox(name, self.node_points_show).on_toggle(|a| ConfigEdit(Change::NodePointsShow(a))), self.node_point_color),
             _ => (checkbox(name, false), [0, 0, 0])
         };
 
         let change_color = move |num: usize| {
-            let color = Slider::new(0..=255, colors[num], move |a| ConfigEdit(Change::ChangeColor(name, num, a)));
+            let color = Slider::new(0..=255, colors[num], move |a| ConfigEdit(Change::Color(name, num, a)));
             let letter = match num {
                 0 => 'R',
                 1 => 'G',
@@ -216,12 +228,13 @@ impl AppConfig {
 }
 
 impl AppConfig {
-    /// Accepts "Circles", "Points", "Prims", "Node points", "Node lines".
+    /// Accepts "Circles", "Points", "Prims", "Regions", "Node points", "Node lines".
     pub fn get_color(&self, name: &'static str) -> iced::Color {
         let array = match name {
             "Circles" => self.circle_color,
             "Points" => self.point_color,
             "Prims" => self.prim_color,
+            "Regions" => self.region_color,
             "Node points" => self.node_point_color,
             "Node lines" => self.node_line_color,
             _ => {
@@ -260,6 +273,102 @@ impl AppConfig {
     }
 }
 
+/// Part of [AppConfig] kept in a project file, see [crate::model::project].
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone)]
+#[serde(default)]
+pub struct ViewState {
+    pub zoom_scale: f32,
+    pub zoom_shift: (f32, f32),
+    /// One of the grid modes: "Circles", "Squares" or "None".
+    pub grid: String,
+    pub bound: bool,
+
+    pub points_show: bool,
+    pub prims_show: bool,
+    pub circles_show: bool,
+    pub regions_show: bool,
+    pub gran_show: bool,
+    pub node_points_show: bool,
+    /// [NodeMode::as_str] of the mode.
+    pub node_mode: String,
+    /// `max` of [NodeMode::Green].
+    pub green_max: f32,
+
+    pub circle_color: [u8; 3],
+    pub point_color: [u8; 3],
+    pub prim_color: [u8; 3],
+    pub region_color: [u8; 3],
+    pub node_point_color: [u8; 3],
+    pub node_line_color: [u8; 3]
+}
+
+impl Default for ViewState {
+    fn default() -> Self {
+        AppConfig::default().view_state()
+    }
+}
+
+impl AppConfig {
+    pub fn view_state(&self) -> ViewState {
+        ViewState {
+            zoom_scale: self.zoom.scale,
+            zoom_shift: (self.zoom.shift.x, self.zoom.shift.y),
+            grid: self.grid.get_display().to_string(),
+            bound: self.bound,
+            points_show: self.points_show,
+            prims_show: self.prims_show,
+            circles_show: self.circles_show,
+            regions_show: self.regions_show,
+            gran_show: self.gran_show,
+            node_points_show: self.node_points_show,
+            node_mode: self.node_mode.as_str().to_string(),
+            green_max: match self.node_mode {
+                NodeMode::Green { max } => max,
+                _ => 2.2
+            },
+            circle_color: self.circle_color,
+            point_color: self.point_color,
+            prim_color: self.prim_color,
+            region_color: self.region_color,
+            node_point_color: self.node_point_color,
+            node_line_color: self.node_line_color
+        }
+    }
+
+    /// Unknown grid and node modes leave the current ones.
+    pub fn apply_view_state(&mut self, view: &ViewState) {
+        if view.zoom_scale.is_finite() && view.zoom_scale > 0. {
+            self.zoom.scale = view.zoom_scale
+        }
+        if view.zoom_shift.0.is_finite() && view.zoom_shift.1.is_finite() {
+            self.zoom.shift = iced::Vector::new(view.zoom_shift.0, view.zoom_shift.1)
+        }
+        if let Some(&grid) = self.grid_modes.iter().find(|&&mode| mode == view.grid) {
+            self.grid.set_display(grid)
+        }
+        self.bound = view.bound;
+        self.points_show = view.points_show;
+        self.prims_show = view.prims_show;
+        self.circles_show = view.circles_show;
+        self.regions_show = view.regions_show;
+        self.gran_show = view.gran_show;
+        self.node_points_show = view.node_points_show;
+        self.node_mode = match view.node_mode.as_str() {
+            "None" => NodeMode::None {},
+            "Pure lines" => NodeMode::PureLines {},
+            "Green" => NodeMode::Green { max: view.green_max },
+            _ => std::mem::replace(&mut self.node_mode, NodeMode::None {})
+        };
+        self.circle_color = view.circle_color;
+        self.point_color = view.point_color;
+        self.prim_color = view.prim_color;
+        self.region_color = view.region_color;
+        self.node_point_color = view.node_point_color;
+        self.node_line_color = view.node_line_color;
+        self.grid.redraw()
+    }
+}
+
 impl Default for AppConfig {
     fn default () -> Self {
         Self {
@@ -273,12 +382,15 @@ impl Default for AppConfig {
             points_show: true,
             prims_show: true,
             circles_show: true,
+            regions_show: true,
+            gran_show: false,
             node_points_show: true,
             node_mode: NodeMode::PureLines {},
 
             circle_color: [0, 0, 255],
             point_color: [0, 0, 0],
             prim_color: [0, 0, 0],
+            region_color: [0, 128, 0],
             node_point_color: [128, 12, 128],
             node_line_color: [0, 0, 0],
 
@@ -14,7 +14,7 @@ impl canvas::Program<Message> for GridDrawing<'_> {
 
     fn draw(&self, _state: &Self::State, renderer: &Renderer, _theme: &Theme, bounds: Rectangle, _cursor: Cursor) -> Vec<Geometry<Renderer>> {
         let content = self.grid_display.cache.draw(renderer, bounds.size(), |frame| {
-            self.grid_info.draw_grid(frame, &self.grid_info);
+            self.grid_info.draw_grid(frame, self.grid_info);
         });
 
         vec![content]
@@ -29,7 +29,7 @@ struct GridDisplay {
 impl GridDisplay {
     fn view<'a>(&'a self, grid_info: &'a GridInfo) -> iced::Element<'a, Message> {
         canvas(GridDrawing {
-            grid_info: &grid_info,
+            grid_info,
             grid_display: self
         })
             .height(Fill)
@@ -96,6 +96,7 @@ impl Default for GridInfo {
     }
 }
 
+#[derive(Default)]
 pub struct Grid {
     display: GridDisplay,
     info: GridInfo
@@ -122,22 +123,13 @@ impl Grid {
 impl Grid {
     pub fn bound(&self, point: &Point) -> Point {
         if self.get_display() != "None" {
-            let mut new_point = Point::default();
-            new_point.x = (point.x / self.info.distance).round() * self.info.distance;
-            new_point.y = (point.y / self.info.distance).round() * self.info.distance;
-            new_point
+            Point::new(
+                (point.x / self.info.distance).round() * self.info.distance,
+                (point.y / self.info.distance).round() * self.info.distance
+            )
         }
         else {
             *point
         }
     }
 }
-
-impl Default for Grid {
-    fn default () -> Self {
-        Self {
-            display: GridDisplay::default(),
-            info: GridInfo::default()
-        }
-    }
-}
@@ -1,5 +1,7 @@
+#[allow(clippy::module_inception)]
 pub mod app_config;
 pub mod grid;
 pub mod zoom;
+pub mod settings;
 
 pub use app_config::AppConfig;
\ No newline at end of file
@@ -0,0 +1,102 @@
+use std::path::PathBuf;
+use crate::math_core::RegionParams;
+
+/// Command line flag with the path to the math core.
+pub const CORE_FLAG: &str = "--core";
+/// Environment variable with the path to the math core.
+pub const CORE_ENV: &str = "VEC_RED_CORE";
+
+/// Where the path to the math core came from.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CoreSource {
+    Flag,
+    Env,
+    Settings,
+    /// Chosen with "Browse".
+    Picker,
+    /// Typed into the path field.
+    Typed
+}
+
+impl CoreSource {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Flag => "command line",
+            Self::Env => CORE_ENV,
+            Self::Settings => "settings file",
+            Self::Picker => "file picker",
+            Self::Typed => "typed"
+        }
+    }
+}
+
+/// Settings kept between sessions.
+#[derive(serde::Serialize, serde::Deserialize, Debug, Default)]
+pub struct Settings {
+    pub core_path: Option<String>,
+    /// Run the math core in a separate process, see [crate::math_core::ProcessCore].
+    #[serde(default)]
+    pub core_process: bool,
+    /// Parameters of the last region created in "Region" mode.
+    #[serde(default)]
+    pub region: RegionParams
+}
+
+impl Settings {
+    /// `$XDG_CONFIG_HOME/vec_red/settings.json`, falling back to `~/.config` or `%APPDATA%`.
+    fn file() -> Option<PathBuf> {
+        let dir = std::env::var_os("XDG_CONFIG_HOME")
+            .map(PathBuf::from)
+            .or_else(|| std::env::var_os("APPDATA").map(PathBuf::from))
+            .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))?;
+        Some(dir.join("vec_red").join("settings.json"))
+    }
+
+    /// Reads settings from disk. Missing or broken file gives defaults.
+    pub fn load() -> Self {
+        Self::file()
+            .and_then(|file| std::fs::read_to_string(file).ok())
+            .and_then(|text| serde_json::from_str(&text).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self) -> bool {
+        let Some(file) = Self::file() else {
+            return false
+        };
+        if let Some(dir) = file.parent() {
+            if std::fs::create_dir_all(dir).is_err() {
+                return false
+            }
+        }
+        match serde_json::to_string_pretty(self) {
+            Ok(text) => std::fs::write(file, text).is_ok(),
+            Err(_) => false
+        }
+    }
+}
+
+/// Value of [CORE_FLAG] in `args`, both `--core path` and `--core=path` are accepted.
+pub fn core_from_args(args: &[String]) -> Option<String> {
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        if arg == CORE_FLAG {
+            return iter.next().cloned()
+        }
+        if let Some(path) = arg.strip_prefix(CORE_FLAG).and_then(|rest| rest.strip_prefix('=')) {
+            return Some(path.to_string())
+        }
+    }
+    None
+}
+
+/// Looks for the math core path: command line flag, then environment variable, then settings file.
+pub fn discover_core(args: &[String], settings: &Settings) -> Option<(String, CoreSource)> {
+    if let Some(path) = core_from_args(args) {
+        return Some((path, CoreSource::Flag))
+    }
+    if let Some(path) = std::env::var(CORE_ENV).ok().filter(|path| !path.trim().is_empty()) {
+        return Some((path, CoreSource::Env))
+    }
+    settings.core_path.clone().map(|path| (path, CoreSource::Settings))
+}
@@ -1,6 +1,6 @@
 mod model;
 mod app;
-mod foreign_functions;
+mod math_core;
 mod app_config;
 
 use app_config::app_config::Change;
@@ -8,6 +8,7 @@ use app_config::app_config::Change;
 use iced::{Point, Size, Vector};
 use iced::widget::text_editor;
 use app::core::VecRed;
+use model::boundary::Boundary;
 
 
 /// Messages produced by [VecRed]
@@ -25,6 +26,8 @@ enum Message {
     ExportModel,
     /// Load model from a file.
     OpenModel,
+    /// Write the view to an SVG file, the whole model if `true`.
+    ExportSvg(bool),
     EditScale(&'static str, f32),
     DeletePoint,
     FindEverything(f64, f64),
@@ -41,20 +44,60 @@ enum Message {
     ConfigOpen(bool),
     ConfigEdit(Change),
 
+    EditCorePath(text_editor::Action),
+    /// Choose the math core library in a file dialog.
+    PickCore,
+    /// Path from the file dialog, `None` if it was closed.
+    CorePicked(Option<String>),
     OpenMathCore,
+    CoreProcess(bool),
+    RestartCore,
     CreateRegion(Point),
-    CreateTriangle
+    /// Load the math core library again and send it the model.
+    ReloadCore,
+    /// Copy the borrowed model into an own one.
+    Detach,
+    FreeCoreOnDetach(bool),
+    ZoomRegion(usize),
+    /// Show the region in the inspector.
+    SelectRegion(usize),
+    /// Check the model with [model::Model::validate] and list the problems.
+    Validate,
+    /// Zoom to the subject of the diagnostic with this index.
+    ZoomDiagnostic(usize),
+    ChooseBoundary(Boundary),
+    /// Set the chosen boundary condition on the chosen prims.
+    ApplyBoundary,
+    UnselectPrims,
+    /// Build the mesh on a worker thread.
+    CreateTriangle,
+    /// The worker has finished, with the result of `FBuildFM`.
+    TriangleBuilt(bool),
+    CancelRun,
+    RunTick(std::time::Instant),
+    /// Look whether the math core is still running.
+    CheckCore
 }
 
 
 fn main() -> iced::Result {
-    let mut settings = iced::window::Settings::default();
-    settings.min_size = Some(Size{width: 700., height: 500.});
+    let args: Vec<String> = std::env::args().collect();
+    if let Some(path) = math_core::host::host_path(&args) {
+        std::process::exit(math_core::host::run(&path))
+    }
+    if app::render::is_render(&args) {
+        std::process::exit(app::render::run(&args))
+    }
+
+    let settings = iced::window::Settings {
+        min_size: Some(Size{width: 700., height: 500.}),
+        ..Default::default()
+    };
     iced::application("VecRed", VecRed::update, VecRed::view)
         .antialiasing(true)
         .window_size(Size::new(1100.0, 900.0))
         .window(settings)
         .theme(|_| iced::Theme::Light)
         .subscription(VecRed::subscription)
-        .run()
+        .run_with(move || (VecRed::new(&args), iced::Task::none()))
 }
@@ -0,0 +1,135 @@
+//! Core host: the editor started with [HOST_FLAG] loads the math core and serves
+//! [Request]s from stdin, so a crash of the core only kills this process.
+
+use std::io::{BufReader, BufWriter};
+use iced::Point;
+use crate::model::borrow_types::records;
+use super::protocol::*;
+use super::{LibCore, MathCore};
+use super::lib_core::known_symbols;
+
+/// Command line flag that turns the editor into a core host for the given library.
+pub const HOST_FLAG: &str = "--core-host";
+
+/// Library path given after [HOST_FLAG].
+pub fn host_path(args: &[String]) -> Option<String> {
+    let position = args.iter().position(|arg| arg == HOST_FLAG)?;
+    args.get(position + 1).cloned()
+}
+
+/// Serves requests until stdin is closed. Returns the exit code of the process.
+pub fn run(path: &str) -> i32 {
+    let mut input = BufReader::new(std::io::stdin().lock());
+    let mut output = BufWriter::new(std::io::stdout().lock());
+
+    let core = match LibCore::open(path) {
+        Ok(core) => core,
+        Err(error) => {
+            let _ = write_message(&mut output, Encoder::default().u8(1).str(&error).0.as_slice());
+            return 1
+        }
+    };
+    if write_message(&mut output, &[0]).is_err() {
+        return 1
+    }
+
+    let host = Host { core };
+    loop {
+        let Ok(payload) = read_message(&mut input) else {
+            return 0
+        };
+        let reply = match Request::decode(&payload) {
+            Ok(request) => host.handle(request),
+            Err(error) => {
+                eprintln!("Core host: {error}");
+                return 1
+            }
+        };
+        if write_message(&mut output, &reply).is_err() {
+            return 1
+        }
+    }
+}
+
+struct Host {
+    core: LibCore
+}
+
+impl Host {
+    fn handle(&self, request: Request) -> Vec<u8> {
+        let core = &self.core;
+        let mut out = Encoder::default();
+        match request {
+            Request::InitModel => core.init_model(),
+            Request::DelMemoModel => core.del_memo_model(),
+            Request::CreatePoint { x, y, r } => { out.i32(core.create_point(Point::new(x, y), r)); }
+            Request::DelPoint(index) => { out.bool(core.del_point(index)); }
+            Request::CreatePrim { prim, typ_prim, vp } => { out.i32(core.create_prim(&prim, typ_prim, vp)); }
+            Request::DelPrim(index) => { out.bool(core.del_prim(index)); }
+            Request::CreateRegion { x, y, params } => { out.i32(core.create_region(&Point::new(x, y), &params)); }
+            Request::BuildFm => { out.bool(core.build_fm()); }
+            Request::BxByBm(index) => {
+                let (bx, by, bm) = core.get_bx_by_bm(index);
+                out.f64(bx).f64(by).f64(bm);
+            }
+            Request::Points => {
+                let (array, len) = core.points_ref();
+                let points = unsafe { records(array, len) };
+                out.i32(points.len() as i32);
+                points.iter().for_each(|point| encode_point(&mut out, point));
+            }
+            Request::Prims => {
+                let (array, len) = core.prims_ref();
+                let prims = unsafe { records(array, len) };
+                out.i32(prims.len() as i32);
+                prims.iter().for_each(|prim| encode_prim(&mut out, prim));
+            }
+            Request::Nodes => {
+                let (array, len) = core.nodes_ref();
+                let nodes = unsafe { records(array, len) };
+                out.i32(nodes.len() as i32);
+                for node in nodes {
+                    let links = unsafe { records(&node.NSW, node.KolSW) };
+                    encode_node(&mut out, node, links);
+                }
+            }
+            Request::Elems => {
+                let (array, len) = core.elems_ref();
+                let elems = unsafe { records(array, len) };
+                out.i32(elems.len() as i32);
+                elems.iter().for_each(|elem| encode_elem(&mut out, elem));
+                // the field goes along, so drawing it takes no call per element
+                for index in 0..elems.len() as i32 {
+                    let (bx, by, bm) = core.get_bx_by_bm(index);
+                    out.f64(bx).f64(by).f64(bm);
+                }
+            }
+            Request::Regions => {
+                let (array, len) = core.regions_ref();
+                let regions = unsafe { records(array, len) };
+                out.i32(regions.len() as i32);
+                for region in regions {
+                    let gran = unsafe { records(&region.Gran, region.KGran) };
+                    encode_region(&mut out, region, gran);
+                }
+            }
+            Request::SetPoint { index, x, y } => core.set_point(index, &Point::new(x, y)),
+            Request::PointXy(x, y) => { out.i32(core.get_point_xy(x, y)); }
+            Request::PrimXy(x, y) => { out.i32(core.get_prim_xy(x, y)); }
+            Request::NodeXy(x, y) => { out.i32(core.get_node_xy(x, y)); }
+            Request::RegionXy(x, y) => { out.i32(core.get_region_xy(x, y)); }
+            Request::OpenDat(path) => { out.bool(core.open_dat(&path)); }
+            Request::SaveDat(path) => { out.bool(core.save_dat(&path)); }
+            Request::Symbols => {
+                let exported = core.symbols();
+                let indices: Vec<u8> = known_symbols()
+                    .enumerate()
+                    .filter(|(_, name)| exported.contains(name))
+                    .map(|(index, _)| index as u8)
+                    .collect();
+                out.bytes(&indices);
+            }
+        }
+        out.0
+    }
+}
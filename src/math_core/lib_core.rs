@@ -0,0 +1,315 @@
+use std::sync::atomic::{AtomicI32, Ordering};
+use iced::Point;
+use libloading::Library;
+use crate::model::borrow_types::*;
+use super::{MathCore, RegionParams};
+
+type CreatePointFn = unsafe extern "C" fn(&f64, &f64, &f64, &u8) -> i32;
+type CreatePrimFn = unsafe extern "C" fn(&i32, &i32, &i32, &u8, &f64) -> i32;
+type CreateRegionFn = unsafe extern "C" fn(&f64, &f64, &f64, &bool, &u8,
+                                           &[u8; 256], &f64, &f64, &f64, &f64, &f64, &f64,
+                                           &bool) -> i32;
+type BxByBmFn = unsafe extern "C" fn(i32, &mut f64, &mut f64, &mut f64);
+type DatFn = unsafe extern "C" fn(&[u8; 256], &[u8; 256]) -> bool;
+type XyFn = unsafe extern "C" fn(f64, f64) -> i32;
+
+/// Every symbol [LibCore] resolves. A library missing any of them is refused.
+pub const REQUIRED_SYMBOLS: [&str; 26] = [
+    "FInitModel", "FDelMemoModel",
+    "FCreatePoint", "FDelPoint", "FCreatePrim", "FDelPrim", "FCreateRegion",
+    "FBuildFM", "FGetBxByBm",
+    "FGetPPoints", "FGetNPoint", "FGetPPrim", "FGetNPrim",
+    "FGetPNode", "FGetNNode", "FGetPElem", "FGetNElem", "FGetPReg",
+    "FOpenDat", "FSaveDat",
+    "FSetXPoint", "FSetYPoint",
+    "FGetPointXY", "FGetPrimXY", "FGetNodeXY", "FGetRegXY"
+];
+
+/// `FGetNReg` is the region count. Older FLib builds do not export it, then [LibCore] counts the regions itself.
+pub const OPTIONAL_SYMBOLS: [&str; 1] = ["FGetNReg"];
+
+/// Names from [REQUIRED_SYMBOLS] that `lib` does not export.
+pub fn missing_symbols(lib: &Library) -> Vec<&'static str> {
+    REQUIRED_SYMBOLS
+        .into_iter()
+        .filter(|name| unsafe { lib.get::<*const ()>(name.as_bytes()).is_err() })
+        .collect()
+}
+
+/// [REQUIRED_SYMBOLS], then [OPTIONAL_SYMBOLS].
+pub fn known_symbols() -> impl Iterator<Item = &'static str> {
+    REQUIRED_SYMBOLS.into_iter().chain(OPTIONAL_SYMBOLS)
+}
+
+/// Names from [known_symbols] that `lib` exports.
+pub fn exported_symbols(lib: &Library) -> Vec<&'static str> {
+    known_symbols()
+        .filter(|name| unsafe { lib.get::<*const ()>(name.as_bytes()).is_ok() })
+        .collect()
+}
+
+/// Function pointers of the math core, resolved once when the library is opened.
+struct VTable {
+    init_model: unsafe extern "C" fn(),
+    del_memo_model: unsafe extern "C" fn(),
+    create_point: CreatePointFn,
+    del_point: unsafe extern "C" fn(i32) -> bool,
+    create_prim: CreatePrimFn,
+    del_prim: unsafe extern "C" fn(i32) -> bool,
+    create_region: CreateRegionFn,
+    build_fm: unsafe extern "C" fn() -> bool,
+    get_bx_by_bm: BxByBmFn,
+    get_p_points: unsafe extern "C" fn() -> *const *mut TBPoint,
+    get_n_point: unsafe extern "C" fn() -> i32,
+    get_p_prim: unsafe extern "C" fn() -> *const *mut TPrimitive,
+    get_n_prim: unsafe extern "C" fn() -> i32,
+    get_p_node: unsafe extern "C" fn() -> *const *mut TNode,
+    get_n_node: unsafe extern "C" fn() -> i32,
+    get_p_elem: unsafe extern "C" fn() -> *const *mut TElement,
+    get_n_elem: unsafe extern "C" fn() -> i32,
+    get_p_reg: unsafe extern "C" fn() -> *const *mut TRegion,
+    get_n_reg: Option<unsafe extern "C" fn() -> i32>,
+    open_dat: DatFn,
+    save_dat: DatFn,
+    set_x_point: unsafe extern "C" fn(i32, f64),
+    set_y_point: unsafe extern "C" fn(i32, f64),
+    get_point_xy: XyFn,
+    get_prim_xy: XyFn,
+    get_node_xy: XyFn,
+    get_reg_xy: XyFn
+}
+
+/// [MathCore] backed by a dynamic library (FLib).
+pub struct LibCore {
+    vtable: VTable,
+    /// Regions created since the last init, used without `FGetNReg`.
+    regions_len: AtomicI32,
+    symbols: Vec<&'static str>,
+    /// Keeps the functions of [VTable] alive.
+    _lib: Library
+}
+
+impl std::fmt::Debug for LibCore {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("LibCore").finish_non_exhaustive()
+    }
+}
+
+impl LibCore {
+    /// Opens the library and resolves every symbol from [REQUIRED_SYMBOLS].
+    pub fn open(path: &str) -> Result<Self, String> {
+        let lib = unsafe { Library::new(path) }.map_err(|error| format!("No library: {error}"))?;
+        let missing = missing_symbols(&lib);
+        if !missing.is_empty() {
+            return Err(format!("Missing symbols: {}", missing.join(", ")))
+        }
+        for name in OPTIONAL_SYMBOLS {
+            if unsafe { lib.get::<*const ()>(name.as_bytes()).is_err() } {
+                eprintln!("Optional symbol {name} is missing")
+            }
+        }
+
+        macro_rules! symbol {
+            ($name:literal) => {
+                *lib.get($name).map_err(|error| format!("{error}"))?
+            };
+        }
+        let vtable = unsafe {
+            VTable {
+                init_model: symbol!(b"FInitModel"),
+                del_memo_model: symbol!(b"FDelMemoModel"),
+                create_point: symbol!(b"FCreatePoint"),
+                del_point: symbol!(b"FDelPoint"),
+                create_prim: symbol!(b"FCreatePrim"),
+                del_prim: symbol!(b"FDelPrim"),
+                create_region: symbol!(b"FCreateRegion"),
+                build_fm: symbol!(b"FBuildFM"),
+                get_bx_by_bm: symbol!(b"FGetBxByBm"),
+                get_p_points: symbol!(b"FGetPPoints"),
+                get_n_point: symbol!(b"FGetNPoint"),
+                get_p_prim: symbol!(b"FGetPPrim"),
+                get_n_prim: symbol!(b"FGetNPrim"),
+                get_p_node: symbol!(b"FGetPNode"),
+                get_n_node: symbol!(b"FGetNNode"),
+                get_p_elem: symbol!(b"FGetPElem"),
+                get_n_elem: symbol!(b"FGetNElem"),
+                get_p_reg: symbol!(b"FGetPReg"),
+                get_n_reg: lib.get(b"FGetNReg").ok().map(|symbol| *symbol),
+                open_dat: symbol!(b"FOpenDat"),
+                save_dat: symbol!(b"FSaveDat"),
+                set_x_point: symbol!(b"FSetXPoint"),
+                set_y_point: symbol!(b"FSetYPoint"),
+                get_point_xy: symbol!(b"FGetPointXY"),
+                get_prim_xy: symbol!(b"FGetPrimXY"),
+                get_node_xy: symbol!(b"FGetNodeXY"),
+                get_reg_xy: symbol!(b"FGetRegXY")
+            }
+        };
+
+        let symbols = exported_symbols(&lib);
+        Ok(Self { vtable, regions_len: AtomicI32::new(0), symbols, _lib: lib })
+    }
+}
+
+/// Splits `path` into the directory (with trailing slash) and the file name, as FLib expects them.
+/// A bare file name is looked up in the current directory.
+fn split_path(path: &str) -> Option<(String, String)> {
+    let (file_dir, file_name) = match path.rfind(['/', '\\']) {
+        Some(slash) => (&path[..=slash], &path[slash + 1..]),
+        None => ("./", path)
+    };
+    if file_name.is_empty() {
+        return None
+    }
+    Some((file_dir.to_string(), file_name.to_string()))
+}
+
+/// File name without its extension, whatever its length.
+fn file_stem(file_name: &str) -> &str {
+    match file_name.rsplit_once('.') {
+        Some((stem, _)) if !stem.is_empty() => stem,
+        _ => file_name
+    }
+}
+
+/// Zero terminated copy of `text`, `None` if it does not fit into the 256 bytes FLib takes.
+fn to_buffer(text: &str) -> Option<[u8; 256]> {
+    let mut buffer: [u8; 256] = [0; 256];
+    if text.len() >= buffer.len() {
+        eprintln!("{text} is longer than {} bytes, FLib cannot take it", buffer.len() - 1);
+        return None
+    }
+    buffer[..text.len()].copy_from_slice(text.as_bytes());
+    Some(buffer)
+}
+
+impl MathCore for LibCore {
+    fn init_model(&self) {
+        self.regions_len.store(0, Ordering::Release);
+        unsafe { (self.vtable.init_model)() }
+    }
+
+    fn del_memo_model(&self) {
+        self.regions_len.store(0, Ordering::Release);
+        unsafe { (self.vtable.del_memo_model)() }
+    }
+
+    fn create_point(&self, point: Point, r: f32) -> i32 {
+        let byte = 0u8;
+        unsafe { (self.vtable.create_point)(&(point.x as f64), &(point.y as f64), &(r as f64), &byte) }
+    }
+
+    fn del_point(&self, index: i32) -> bool {
+        unsafe { (self.vtable.del_point)(index) }
+    }
+
+    fn create_prim(&self, prim: &[i32; 3], typ_prim: u8, vp: f64) -> i32 {
+        unsafe { (self.vtable.create_prim)(&prim[0], &prim[1], &prim[2], &typ_prim, &vp) }
+    }
+
+    fn del_prim(&self, index: i32) -> bool {
+        unsafe { (self.vtable.del_prim)(index) }
+    }
+
+    fn create_region(&self, point: &Point, params: &RegionParams) -> i32 {
+        let xp= point.x as f64;
+        let yp = point.y as f64;
+        let p = params;
+        let Some(name) = to_buffer(&p.mat_char_name) else {
+            return -1
+        };
+        let index = unsafe {
+            (self.vtable.create_region)(&xp, &yp, &p.r, &p.tri_w, &p.pr_mag, &name,
+                                        &p.mu, &p.px, &p.py, &p.w, &p.ip, &p.plot, &p.jby_iw)
+        };
+        self.regions_len.fetch_max(index + 1, Ordering::AcqRel);
+        index
+    }
+
+    fn build_fm(&self) -> bool {
+        unsafe { (self.vtable.build_fm)() }
+    }
+
+    fn get_bx_by_bm(&self, index: i32) -> (f64, f64, f64) {
+        let mut bx = 0.;
+        let mut by = 0.;
+        let mut bm = 0.;
+        unsafe { (self.vtable.get_bx_by_bm)(index, &mut bx, &mut by, &mut bm) }
+        (bx, by, bm)
+    }
+
+    fn points_ref(&self) -> (*const *mut TBPoint, i32) {
+        unsafe { ((self.vtable.get_p_points)(), (self.vtable.get_n_point)()) }
+    }
+
+    fn prims_ref(&self) -> (*const *mut TPrimitive, i32) {
+        unsafe { ((self.vtable.get_p_prim)(), (self.vtable.get_n_prim)()) }
+    }
+
+    fn nodes_ref(&self) -> (*const *mut TNode, i32) {
+        unsafe { ((self.vtable.get_p_node)(), (self.vtable.get_n_node)()) }
+    }
+
+    fn elems_ref(&self) -> (*const *mut TElement, i32) {
+        unsafe { ((self.vtable.get_p_elem)(), (self.vtable.get_n_elem)()) }
+    }
+
+    fn regions_ref(&self) -> (*const *mut TRegion, i32) {
+        let len = match self.vtable.get_n_reg {
+            Some(get_n_reg) => unsafe { get_n_reg() },
+            None => self.regions_len.load(Ordering::Acquire)
+        };
+        unsafe { ((self.vtable.get_p_reg)(), len) }
+    }
+
+    fn set_point(&self, index: i32, point: &Point) {
+        unsafe {
+            (self.vtable.set_x_point)(index, point.x as f64);
+            (self.vtable.set_y_point)(index, point.y as f64);
+        }
+    }
+
+    fn get_point_xy(&self, x: f64, y: f64) -> i32 {
+        unsafe { (self.vtable.get_point_xy)(x, y) }
+    }
+
+    fn get_prim_xy(&self, x: f64, y: f64) -> i32 {
+        unsafe { (self.vtable.get_prim_xy)(x, y) }
+    }
+
+    fn get_node_xy(&self, x: f64, y: f64) -> i32 {
+        unsafe { (self.vtable.get_node_xy)(x, y) }
+    }
+
+    fn get_region_xy(&self, x: f64, y: f64) -> i32 {
+        unsafe { (self.vtable.get_reg_xy)(x, y) }
+    }
+
+    fn open_dat(&self, path: &str) -> bool {
+        let Some((file_dir, file_name)) = split_path(path) else {
+            return false
+        };
+        let (Some(arr_name), Some(arr_dir)) = (to_buffer(file_stem(&file_name)), to_buffer(&file_dir)) else {
+            return false
+        };
+        if self.vtable.get_n_reg.is_none() {
+            eprintln!("FGetNReg is missing, the regions of {file_name} are not shown");
+            self.regions_len.store(0, Ordering::Release);
+        }
+        unsafe { (self.vtable.open_dat)(&arr_dir, &arr_name) }
+    }
+
+    fn save_dat(&self, path: &str) -> bool {
+        let Some((file_dir, file_name)) = split_path(path) else {
+            return false
+        };
+        let (Some(arr_name), Some(arr_dir)) = (to_buffer(&file_name), to_buffer(&file_dir)) else {
+            return false
+        };
+        unsafe { (self.vtable.save_dat)(&arr_dir, &arr_name) }
+    }
+
+    fn symbols(&self) -> Vec<&'static str> {
+        self.symbols.clone()
+    }
+}
@@ -0,0 +1,74 @@
+pub mod lib_core;
+pub mod process_core;
+pub mod protocol;
+pub mod host;
+pub mod region;
+
+use iced::Point;
+use crate::model::borrow_types::*;
+
+pub use lib_core::LibCore;
+pub use process_core::ProcessCore;
+pub use region::RegionParams;
+
+/// Operations of the math core. Everything [crate::model::Model] and [crate::app::core::VecRed] need from it.
+///
+/// The `*_ref` functions return the address of the core's array variable and its length.
+/// The arrays stay owned by the core and may move after any mutating call.
+///
+/// A core may be moved to a worker thread for [MathCore::build_fm], but it is never called
+/// from two threads at once: the editor blocks every other call while a run is going.
+pub trait MathCore: std::fmt::Debug + Send + Sync {
+    /// Prepares an empty model inside the core.
+    fn init_model(&self);
+    /// Frees everything the core holds.
+    fn del_memo_model(&self);
+
+    /// Returns the index of the new point.
+    fn create_point(&self, point: Point, r: f32) -> i32;
+    fn del_point(&self, index: i32) -> bool;
+    /// Returns the index of the new prim. `typ_prim` and `vp` are its boundary condition.
+    fn create_prim(&self, prim: &[i32; 3], typ_prim: u8, vp: f64) -> i32;
+    fn del_prim(&self, index: i32) -> bool;
+    /// Returns the index of the new region.
+    fn create_region(&self, point: &Point, params: &RegionParams) -> i32;
+
+    /// Builds the mesh.
+    fn build_fm(&self) -> bool;
+    /// Magnetic induction in the element: Bx, By, Bm.
+    fn get_bx_by_bm(&self, index: i32) -> (f64, f64, f64);
+    fn get_bm_only(&self, index: i32) -> f32 {
+        self.get_bx_by_bm(index).2 as f32
+    }
+
+    fn points_ref(&self) -> (*const *mut TBPoint, i32);
+    fn prims_ref(&self) -> (*const *mut TPrimitive, i32);
+    fn nodes_ref(&self) -> (*const *mut TNode, i32);
+    fn elems_ref(&self) -> (*const *mut TElement, i32);
+    fn regions_ref(&self) -> (*const *mut TRegion, i32);
+
+    fn set_point(&self, index: i32, point: &Point);
+
+    /// Index of the entity at (x, y), -1 if there is none.
+    fn get_point_xy(&self, x: f64, y: f64) -> i32;
+    fn get_prim_xy(&self, x: f64, y: f64) -> i32;
+    fn get_node_xy(&self, x: f64, y: f64) -> i32;
+    fn get_region_xy(&self, x: f64, y: f64) -> i32;
+
+    /// Loads a `.mke`/`.fmd` file into the core.
+    fn open_dat(&self, path: &str) -> bool;
+    fn save_dat(&self, path: &str) -> bool;
+
+    /// Names from [lib_core::known_symbols] the library exports.
+    fn symbols(&self) -> Vec<&'static str>;
+
+    /// False once the core can not answer anymore.
+    fn is_alive(&self) -> bool {
+        true
+    }
+
+    /// Interrupts a running call from another thread. False if the core can not be interrupted.
+    fn cancel(&self) -> bool {
+        false
+    }
+}
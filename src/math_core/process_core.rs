@@ -0,0 +1,346 @@
+use std::io::{BufReader, BufWriter};
+use std::process::{Child, ChildStdin, ChildStdout, Command, Stdio};
+use std::ptr::addr_of;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicBool, Ordering};
+use iced::Point;
+use crate::model::borrow_types::*;
+use super::host::HOST_FLAG;
+use super::lib_core::known_symbols;
+use super::protocol::*;
+use super::{MathCore, RegionParams};
+
+/// Local copies of the arrays of the core, the `*_ref` functions point into them.
+///
+/// Every `p_*` field plays the role of the core's array variable.
+struct Mirror {
+    points: Vec<TBPoint>,
+    p_points: *mut TBPoint,
+    prims: Vec<TPrimitive>,
+    p_prims: *mut TPrimitive,
+    nodes: Vec<TNode>,
+    p_nodes: *mut TNode,
+    node_links: Vec<Vec<i32>>,
+    elems: Vec<TElement>,
+    p_elems: *mut TElement,
+    /// Bx, By and Bm of every element, fetched with them.
+    fields: Vec<(f64, f64, f64)>,
+    regions: Vec<TRegion>,
+    p_regions: *mut TRegion,
+    grans: Vec<Vec<TGran>>
+}
+
+/// The pointers only point into the vectors of the same [Mirror].
+unsafe impl Send for Mirror {}
+
+impl Default for Mirror {
+    fn default() -> Self {
+        let mut mirror = Self {
+            points: vec![],
+            p_points: std::ptr::null_mut(),
+            prims: vec![],
+            p_prims: std::ptr::null_mut(),
+            nodes: vec![],
+            p_nodes: std::ptr::null_mut(),
+            node_links: vec![],
+            elems: vec![],
+            p_elems: std::ptr::null_mut(),
+            fields: vec![],
+            regions: vec![],
+            p_regions: std::ptr::null_mut(),
+            grans: vec![]
+        };
+        mirror.sync();
+        mirror
+    }
+}
+
+impl Mirror {
+    fn sync(&mut self) {
+        self.p_points = self.points.as_mut_ptr();
+        self.p_prims = self.prims.as_mut_ptr();
+        self.p_nodes = self.nodes.as_mut_ptr();
+        self.p_elems = self.elems.as_mut_ptr();
+        self.p_regions = self.regions.as_mut_ptr();
+    }
+}
+
+struct Connection {
+    input: BufWriter<ChildStdin>,
+    output: BufReader<ChildStdout>
+}
+
+/// [MathCore] living in a child process started with [HOST_FLAG].
+///
+/// A crash of the library only kills the child, after that every call returns a default value
+/// and [MathCore::is_alive] turns false. The last fetched arrays stay readable.
+/// [MathCore::cancel] kills the child the same way.
+pub struct ProcessCore {
+    /// Kept apart from [Connection], so the child can be killed while a call waits for it.
+    child: Mutex<Child>,
+    connection: Mutex<Connection>,
+    mirror: Mutex<Box<Mirror>>,
+    alive: AtomicBool
+}
+
+impl std::fmt::Debug for ProcessCore {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ProcessCore").field("alive", &self.alive).finish_non_exhaustive()
+    }
+}
+
+impl ProcessCore {
+    /// Starts the host for the library at `path` and waits until it is loaded.
+    pub fn spawn(path: &str) -> Result<Self, String> {
+        let exe = std::env::current_exe().map_err(|error| format!("No editor executable: {error}"))?;
+        Self::spawn_host(&exe, path)
+    }
+
+    /// [ProcessCore::spawn] with another editor executable as the host, the tests run it from the test binary.
+    pub(crate) fn spawn_host(exe: &std::path::Path, path: &str) -> Result<Self, String> {
+        let mut child = Command::new(exe)
+            .arg(HOST_FLAG)
+            .arg(path)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()
+            .map_err(|error| format!("Core host is not started: {error}"))?;
+        let input = BufWriter::new(child.stdin.take().unwrap());
+        let mut output = BufReader::new(child.stdout.take().unwrap());
+
+        let hello = read_message(&mut output).map_err(|error| format!("Core host is not answering: {error}"))?;
+        let mut hello = Decoder(&hello);
+        if !matches!(hello.u8(), Ok(0)) {
+            let _ = child.wait();
+            return Err(hello.string().unwrap_or_else(|_| String::from("Core host has failed")))
+        }
+
+        Ok(Self {
+            child: Mutex::new(child),
+            connection: Mutex::new(Connection { input, output }),
+            mirror: Mutex::new(Box::default()),
+            alive: AtomicBool::new(true)
+        })
+    }
+
+    /// Sends `request` and waits for the reply. `None` once the host is dead.
+    fn call(&self, request: Request) -> Option<Vec<u8>> {
+        if !self.alive.load(Ordering::Acquire) {
+            return None
+        }
+        let mut connection = self.connection.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        let connection = &mut *connection;
+        let reply = write_message(&mut connection.input, &request.encode())
+            .and_then(|_| read_message(&mut connection.output));
+        match reply {
+            Ok(reply) => Some(reply),
+            Err(error) => {
+                println!("Math core has stopped: {error}");
+                self.stop();
+                None
+            }
+        }
+    }
+
+    fn call_i32(&self, request: Request) -> i32 {
+        self.call(request).and_then(|reply| Decoder(&reply).i32().ok()).unwrap_or(-1)
+    }
+
+    fn call_bool(&self, request: Request) -> bool {
+        self.call(request).and_then(|reply| Decoder(&reply).bool().ok()).unwrap_or(false)
+    }
+
+    /// Runs `f` on the mirror and the decoded reply, if both are there.
+    fn fetch<T>(&self, request: Request, f: impl FnOnce(&mut Mirror, &mut Decoder) -> std::io::Result<T>) -> Option<T> {
+        let reply = self.call(request)?;
+        let mut mirror = self.mirror.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        let result = f(&mut mirror, &mut Decoder(&reply)).ok();
+        mirror.sync();
+        result
+    }
+
+    fn mirror(&self) -> std::sync::MutexGuard<'_, Box<Mirror>> {
+        self.mirror.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    fn child(&self) -> std::sync::MutexGuard<'_, Child> {
+        self.child.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    /// Kills the host, a call waiting for it gets an error and returns the default value.
+    fn stop(&self) {
+        self.alive.store(false, Ordering::Release);
+        let mut child = self.child();
+        let _ = child.kill();
+        let _ = child.wait();
+    }
+}
+
+impl Drop for ProcessCore {
+    fn drop(&mut self) {
+        self.stop()
+    }
+}
+
+impl MathCore for ProcessCore {
+    fn init_model(&self) {
+        self.call(Request::InitModel);
+    }
+
+    fn del_memo_model(&self) {
+        self.call(Request::DelMemoModel);
+    }
+
+    fn create_point(&self, point: Point, r: f32) -> i32 {
+        self.call_i32(Request::CreatePoint { x: point.x, y: point.y, r })
+    }
+
+    fn del_point(&self, index: i32) -> bool {
+        self.call_bool(Request::DelPoint(index))
+    }
+
+    fn create_prim(&self, prim: &[i32; 3], typ_prim: u8, vp: f64) -> i32 {
+        self.call_i32(Request::CreatePrim { prim: *prim, typ_prim, vp })
+    }
+
+    fn del_prim(&self, index: i32) -> bool {
+        self.call_bool(Request::DelPrim(index))
+    }
+
+    fn create_region(&self, point: &Point, params: &RegionParams) -> i32 {
+        self.call_i32(Request::CreateRegion { x: point.x, y: point.y, params: params.clone() })
+    }
+
+    fn build_fm(&self) -> bool {
+        self.call_bool(Request::BuildFm)
+    }
+
+    /// The field of the last fetched elements, the core is only asked for an element not fetched yet.
+    fn get_bx_by_bm(&self, index: i32) -> (f64, f64, f64) {
+        let fetched = usize::try_from(index).ok().and_then(|index| self.mirror().fields.get(index).copied());
+        if let Some(field) = fetched {
+            return field
+        }
+        self.call(Request::BxByBm(index))
+            .and_then(|reply| {
+                let mut reply = Decoder(&reply);
+                Some((reply.f64().ok()?, reply.f64().ok()?, reply.f64().ok()?))
+            })
+            .unwrap_or_default()
+    }
+
+    fn points_ref(&self) -> (*const *mut TBPoint, i32) {
+        self.fetch(Request::Points, |mirror, reply| {
+            mirror.points = (0..reply.len()?).map(|_| decode_point(reply)).collect::<Result<_, _>>()?;
+            Ok(())
+        });
+        let mirror = self.mirror();
+        (addr_of!(mirror.p_points), mirror.points.len() as i32)
+    }
+
+    fn prims_ref(&self) -> (*const *mut TPrimitive, i32) {
+        self.fetch(Request::Prims, |mirror, reply| {
+            mirror.prims = (0..reply.len()?).map(|_| decode_prim(reply)).collect::<Result<_, _>>()?;
+            Ok(())
+        });
+        let mirror = self.mirror();
+        (addr_of!(mirror.p_prims), mirror.prims.len() as i32)
+    }
+
+    fn nodes_ref(&self) -> (*const *mut TNode, i32) {
+        self.fetch(Request::Nodes, |mirror, reply| {
+            let (mut nodes, mut links): (Vec<TNode>, Vec<Vec<i32>>) =
+                (0..reply.len()?).map(|_| decode_node(reply)).collect::<Result<Vec<_>, _>>()?.into_iter().unzip();
+            for (node, links) in nodes.iter_mut().zip(links.iter_mut()) {
+                node.NSW = links.as_mut_ptr();
+            }
+            mirror.nodes = nodes;
+            mirror.node_links = links;
+            Ok(())
+        });
+        let mirror = self.mirror();
+        (addr_of!(mirror.p_nodes), mirror.nodes.len() as i32)
+    }
+
+    fn elems_ref(&self) -> (*const *mut TElement, i32) {
+        self.fetch(Request::Elems, |mirror, reply| {
+            mirror.elems = (0..reply.len()?).map(|_| decode_elem(reply)).collect::<Result<_, _>>()?;
+            mirror.fields = mirror.elems.iter()
+                .map(|_| Ok((reply.f64()?, reply.f64()?, reply.f64()?)))
+                .collect::<std::io::Result<_>>()?;
+            Ok(())
+        });
+        let mirror = self.mirror();
+        (addr_of!(mirror.p_elems), mirror.elems.len() as i32)
+    }
+
+    fn regions_ref(&self) -> (*const *mut TRegion, i32) {
+        self.fetch(Request::Regions, |mirror, reply| {
+            let (mut regions, mut grans): (Vec<TRegion>, Vec<Vec<TGran>>) =
+                (0..reply.len()?).map(|_| decode_region(reply)).collect::<Result<Vec<_>, _>>()?.into_iter().unzip();
+            for (region, gran) in regions.iter_mut().zip(grans.iter_mut()) {
+                region.Gran = gran.as_mut_ptr();
+            }
+            mirror.regions = regions;
+            mirror.grans = grans;
+            Ok(())
+        });
+        let mirror = self.mirror();
+        (addr_of!(mirror.p_regions), mirror.regions.len() as i32)
+    }
+
+    fn set_point(&self, index: i32, point: &Point) {
+        self.call(Request::SetPoint { index, x: point.x, y: point.y });
+    }
+
+    fn get_point_xy(&self, x: f64, y: f64) -> i32 {
+        self.call_i32(Request::PointXy(x, y))
+    }
+
+    fn get_prim_xy(&self, x: f64, y: f64) -> i32 {
+        self.call_i32(Request::PrimXy(x, y))
+    }
+
+    fn get_node_xy(&self, x: f64, y: f64) -> i32 {
+        self.call_i32(Request::NodeXy(x, y))
+    }
+
+    fn get_region_xy(&self, x: f64, y: f64) -> i32 {
+        self.call_i32(Request::RegionXy(x, y))
+    }
+
+    fn open_dat(&self, path: &str) -> bool {
+        self.call_bool(Request::OpenDat(path.to_string()))
+    }
+
+    fn save_dat(&self, path: &str) -> bool {
+        self.call_bool(Request::SaveDat(path.to_string()))
+    }
+
+    fn symbols(&self) -> Vec<&'static str> {
+        let indices = self.call(Request::Symbols)
+            .and_then(|reply| Decoder(&reply).bytes().ok())
+            .unwrap_or_default();
+        known_symbols()
+            .enumerate()
+            .filter(|(index, _)| indices.contains(&(*index as u8)))
+            .map(|(_, name)| name)
+            .collect()
+    }
+
+    fn is_alive(&self) -> bool {
+        if !self.alive.load(Ordering::Acquire) {
+            return false
+        }
+        if let Ok(Some(_)) = self.child().try_wait() {
+            self.alive.store(false, Ordering::Release);
+            return false
+        }
+        true
+    }
+
+    fn cancel(&self) -> bool {
+        self.stop();
+        true
+    }
+}
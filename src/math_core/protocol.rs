@@ -0,0 +1,380 @@
+//! Messages between the editor and the core host process.
+//!
+//! Every message is a little-endian `u32` length followed by the payload.
+//! A request payload starts with the tag of [Request], the reply payload holds only the result.
+
+use std::io::{self, Read, Write};
+use std::ptr::null_mut;
+use crate::model::borrow_types::*;
+use super::RegionParams;
+
+/// One call of [super::MathCore].
+#[derive(Debug)]
+pub enum Request {
+    InitModel,
+    DelMemoModel,
+    CreatePoint { x: f32, y: f32, r: f32 },
+    DelPoint(i32),
+    CreatePrim { prim: [i32; 3], typ_prim: u8, vp: f64 },
+    DelPrim(i32),
+    CreateRegion { x: f32, y: f32, params: RegionParams },
+    BuildFm,
+    BxByBm(i32),
+    Points,
+    Prims,
+    Nodes,
+    /// Reply: the elements, then Bx, By and Bm of each of them.
+    Elems,
+    Regions,
+    SetPoint { index: i32, x: f32, y: f32 },
+    PointXy(f64, f64),
+    PrimXy(f64, f64),
+    NodeXy(f64, f64),
+    RegionXy(f64, f64),
+    OpenDat(String),
+    SaveDat(String),
+    /// Reply: indices in [super::lib_core::known_symbols] of the exported symbols.
+    Symbols
+}
+
+/// Builds a payload.
+#[derive(Default)]
+pub struct Encoder(pub Vec<u8>);
+
+impl Encoder {
+    pub fn u8(&mut self, value: u8) -> &mut Self {
+        self.0.push(value);
+        self
+    }
+    pub fn bool(&mut self, value: bool) -> &mut Self {
+        self.u8(value as u8)
+    }
+    pub fn i16(&mut self, value: i16) -> &mut Self {
+        self.0.extend_from_slice(&value.to_le_bytes());
+        self
+    }
+    pub fn i32(&mut self, value: i32) -> &mut Self {
+        self.0.extend_from_slice(&value.to_le_bytes());
+        self
+    }
+    pub fn f32(&mut self, value: f32) -> &mut Self {
+        self.0.extend_from_slice(&value.to_le_bytes());
+        self
+    }
+    pub fn f64(&mut self, value: f64) -> &mut Self {
+        self.0.extend_from_slice(&value.to_le_bytes());
+        self
+    }
+    pub fn bytes(&mut self, value: &[u8]) -> &mut Self {
+        self.i32(value.len() as i32);
+        self.0.extend_from_slice(value);
+        self
+    }
+    pub fn str(&mut self, value: &str) -> &mut Self {
+        self.bytes(value.as_bytes())
+    }
+}
+
+/// Reads a payload. Every getter fails once the payload is exhausted.
+pub struct Decoder<'a>(pub &'a [u8]);
+
+impl Decoder<'_> {
+    fn take<const N: usize>(&mut self) -> io::Result<[u8; N]> {
+        if self.0.len() < N {
+            return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "short message"))
+        }
+        let (head, tail) = self.0.split_at(N);
+        self.0 = tail;
+        Ok(head.try_into().unwrap())
+    }
+    pub fn u8(&mut self) -> io::Result<u8> {
+        self.take::<1>().map(|byte| byte[0])
+    }
+    pub fn bool(&mut self) -> io::Result<bool> {
+        self.u8().map(|byte| byte != 0)
+    }
+    pub fn i16(&mut self) -> io::Result<i16> {
+        self.take().map(i16::from_le_bytes)
+    }
+    pub fn i32(&mut self) -> io::Result<i32> {
+        self.take().map(i32::from_le_bytes)
+    }
+    pub fn f32(&mut self) -> io::Result<f32> {
+        self.take().map(f32::from_le_bytes)
+    }
+    pub fn f64(&mut self) -> io::Result<f64> {
+        self.take().map(f64::from_le_bytes)
+    }
+    pub fn len(&mut self) -> io::Result<usize> {
+        usize::try_from(self.i32()?).map_err(|_| io::Error::new(io::ErrorKind::InvalidData, "negative length"))
+    }
+    pub fn bytes(&mut self) -> io::Result<Vec<u8>> {
+        let len = self.len()?;
+        if self.0.len() < len {
+            return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "short message"))
+        }
+        let (head, tail) = self.0.split_at(len);
+        self.0 = tail;
+        Ok(head.to_vec())
+    }
+    pub fn string(&mut self) -> io::Result<String> {
+        String::from_utf8(self.bytes()?).map_err(|_| io::Error::new(io::ErrorKind::InvalidData, "not utf-8"))
+    }
+}
+
+pub fn write_message(out: &mut impl Write, payload: &[u8]) -> io::Result<()> {
+    out.write_all(&(payload.len() as u32).to_le_bytes())?;
+    out.write_all(payload)?;
+    out.flush()
+}
+
+/// Longest payload [read_message] takes, a longer length in the header means a broken stream.
+pub const MAX_MESSAGE_LEN: usize = 1 << 30;
+
+/// The buffer grows with the bytes that really come, not with the length in the header.
+pub fn read_message(input: &mut impl Read) -> io::Result<Vec<u8>> {
+    let mut len = [0u8; 4];
+    input.read_exact(&mut len)?;
+    let len = u32::from_le_bytes(len) as usize;
+    if len > MAX_MESSAGE_LEN {
+        return Err(io::Error::new(io::ErrorKind::InvalidData, format!("message of {len} bytes")))
+    }
+    let mut payload = Vec::new();
+    input.take(len as u64).read_to_end(&mut payload)?;
+    if payload.len() < len {
+        return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "message is cut"))
+    }
+    Ok(payload)
+}
+
+impl Request {
+    pub fn encode(&self) -> Vec<u8> {
+        let mut out = Encoder::default();
+        match self {
+            Self::InitModel => { out.u8(0); }
+            Self::DelMemoModel => { out.u8(1); }
+            Self::CreatePoint { x, y, r } => { out.u8(2).f32(*x).f32(*y).f32(*r); }
+            Self::DelPoint(index) => { out.u8(3).i32(*index); }
+            Self::CreatePrim { prim: p, typ_prim, vp } => { out.u8(4).i32(p[0]).i32(p[1]).i32(p[2]).u8(*typ_prim).f64(*vp); }
+            Self::DelPrim(index) => { out.u8(5).i32(*index); }
+            Self::CreateRegion { x, y, params } => { encode_region_params(out.u8(6).f32(*x).f32(*y), params); }
+            Self::BuildFm => { out.u8(7); }
+            Self::BxByBm(index) => { out.u8(8).i32(*index); }
+            Self::Points => { out.u8(9); }
+            Self::Prims => { out.u8(10); }
+            Self::Nodes => { out.u8(11); }
+            Self::Elems => { out.u8(12); }
+            Self::Regions => { out.u8(13); }
+            Self::SetPoint { index, x, y } => { out.u8(14).i32(*index).f32(*x).f32(*y); }
+            Self::PointXy(x, y) => { out.u8(15).f64(*x).f64(*y); }
+            Self::PrimXy(x, y) => { out.u8(16).f64(*x).f64(*y); }
+            Self::NodeXy(x, y) => { out.u8(17).f64(*x).f64(*y); }
+            Self::RegionXy(x, y) => { out.u8(18).f64(*x).f64(*y); }
+            Self::OpenDat(path) => { out.u8(19).str(path); }
+            Self::SaveDat(path) => { out.u8(20).str(path); }
+            Self::Symbols => { out.u8(21); }
+        }
+        out.0
+    }
+
+    pub fn decode(payload: &[u8]) -> io::Result<Self> {
+        let mut input = Decoder(payload);
+        let request = match input.u8()? {
+            0 => Self::InitModel,
+            1 => Self::DelMemoModel,
+            2 => Self::CreatePoint { x: input.f32()?, y: input.f32()?, r: input.f32()? },
+            3 => Self::DelPoint(input.i32()?),
+            4 => Self::CreatePrim { prim: [input.i32()?, input.i32()?, input.i32()?], typ_prim: input.u8()?, vp: input.f64()? },
+            5 => Self::DelPrim(input.i32()?),
+            6 => Self::CreateRegion { x: input.f32()?, y: input.f32()?, params: decode_region_params(&mut input)? },
+            7 => Self::BuildFm,
+            8 => Self::BxByBm(input.i32()?),
+            9 => Self::Points,
+            10 => Self::Prims,
+            11 => Self::Nodes,
+            12 => Self::Elems,
+            13 => Self::Regions,
+            14 => Self::SetPoint { index: input.i32()?, x: input.f32()?, y: input.f32()? },
+            15 => Self::PointXy(input.f64()?, input.f64()?),
+            16 => Self::PrimXy(input.f64()?, input.f64()?),
+            17 => Self::NodeXy(input.f64()?, input.f64()?),
+            18 => Self::RegionXy(input.f64()?, input.f64()?),
+            19 => Self::OpenDat(input.string()?),
+            20 => Self::SaveDat(input.string()?),
+            21 => Self::Symbols,
+            tag => return Err(io::Error::new(io::ErrorKind::InvalidData, format!("unknown request {tag}")))
+        };
+        Ok(request)
+    }
+}
+
+pub fn encode_point(out: &mut Encoder, point: &TBPoint) {
+    out.f64(point.x).f64(point.y).f64(point.r).u8(point.TypPoint).f64(point.Vp).f64(point.Ip).i32(point.NNode);
+}
+
+pub fn decode_point(input: &mut Decoder) -> io::Result<TBPoint> {
+    Ok(TBPoint {
+        x: input.f64()?,
+        y: input.f64()?,
+        r: input.f64()?,
+        TypPoint: input.u8()?,
+        Vp: input.f64()?,
+        Ip: input.f64()?,
+        NNode: input.i32()?
+    })
+}
+
+pub fn encode_prim(out: &mut Encoder, prim: &TPrimitive) {
+    out.i32(prim.p[0]).i32(prim.p[1]).i32(prim.p[2]).u8(prim.TypPrim).bool(prim.IsFront).f64(prim.Vp).f64(prim.Ip);
+}
+
+pub fn decode_prim(input: &mut Decoder) -> io::Result<TPrimitive> {
+    Ok(TPrimitive {
+        p: [input.i32()?, input.i32()?, input.i32()?],
+        TypPrim: input.u8()?,
+        IsFront: input.bool()?,
+        Vp: input.f64()?,
+        Ip: input.f64()?
+    })
+}
+
+/// `links` are the `NSW` neighbours of the node.
+pub fn encode_node(out: &mut Encoder, node: &TNode, links: &[i32]) {
+    out.f64(node.x).f64(node.y).f64(node.VP).u8(node.TypNode).i32(node.PNode);
+    out.i32(links.len() as i32);
+    for &link in links {
+        out.i32(link);
+    }
+    out.f64(node.F).f64(node.Yp);
+}
+
+/// The returned node has no pointers yet, `NSW` has to be pointed at the returned links.
+pub fn decode_node(input: &mut Decoder) -> io::Result<(TNode, Vec<i32>)> {
+    let (x, y, vp, typ_node, p_node) = (input.f64()?, input.f64()?, input.f64()?, input.u8()?, input.i32()?);
+    let mut links = vec![];
+    for _ in 0..input.len()? {
+        links.push(input.i32()?);
+    }
+    let node = TNode {
+        x, y,
+        VP: vp,
+        TypNode: typ_node,
+        PNode: p_node,
+        KolSW: links.len() as i32,
+        NSW: null_mut(),
+        KolSI: 0,
+        NSI: null_mut(),
+        NSK: null_mut(),
+        vKolSWMemo: links.len() as i32,
+        vKolSIMemo: 0,
+        F: input.f64()?,
+        Yp: input.f64()?
+    };
+    Ok((node, links))
+}
+
+pub fn encode_elem(out: &mut Encoder, elem: &TElement) {
+    out.i32(elem.m[0]).i32(elem.m[1]).i32(elem.m[2]).i16(elem.IZP);
+    for value in [elem.Px, elem.Py, elem.XNJU, elem.A1, elem.Delta, elem.xs, elem.ys] {
+        out.f64(value);
+    }
+    for value in elem.S.iter().chain(&elem.A).chain(&elem.B).chain(&elem.C) {
+        out.f64(*value);
+    }
+}
+
+pub fn decode_elem(input: &mut Decoder) -> io::Result<TElement> {
+    let mut elem = TElement {
+        m: [input.i32()?, input.i32()?, input.i32()?],
+        IZP: input.i16()?,
+        Px: input.f64()?,
+        Py: input.f64()?,
+        XNJU: input.f64()?,
+        A1: input.f64()?,
+        Delta: input.f64()?,
+        xs: input.f64()?,
+        ys: input.f64()?,
+        S: [0.; 6],
+        A: [0.; 3],
+        B: [0.; 3],
+        C: [0.; 3]
+    };
+    for value in elem.S.iter_mut().chain(&mut elem.A).chain(&mut elem.B).chain(&mut elem.C) {
+        *value = input.f64()?;
+    }
+    Ok(elem)
+}
+
+pub fn encode_region_params(out: &mut Encoder, params: &RegionParams) {
+    out.f64(params.r).bool(params.tri_w).u8(params.pr_mag).str(&params.mat_char_name).f64(params.mu);
+    for value in [params.px, params.py, params.w, params.ip, params.plot] {
+        out.f64(value);
+    }
+    out.bool(params.jby_iw);
+}
+
+pub fn decode_region_params(input: &mut Decoder) -> io::Result<RegionParams> {
+    Ok(RegionParams {
+        r: input.f64()?,
+        tri_w: input.bool()?,
+        pr_mag: input.u8()?,
+        mat_char_name: input.string()?,
+        mu: input.f64()?,
+        px: input.f64()?,
+        py: input.f64()?,
+        w: input.f64()?,
+        ip: input.f64()?,
+        plot: input.f64()?,
+        jby_iw: input.bool()?
+    })
+}
+
+/// `gran` is the boundary of the region.
+pub fn encode_region(out: &mut Encoder, region: &TRegion, gran: &[TGran]) {
+    out.f64(region.x).f64(region.y).f64(region.R).bool(region.TriW).f64(region.CNu).u8(region.PrMag);
+    for value in [region.Px, region.Py, region.W, region.Ip, region.ST, region.PLOT] {
+        out.f64(value);
+    }
+    out.bool(region.JbyIW);
+    out.0.extend_from_slice(&region.MatCharName);
+    out.i32(region.NMatChar);
+    out.i32(gran.len() as i32);
+    for gran in gran {
+        out.i32(gran.Node).f64(gran.UgUzl);
+    }
+}
+
+/// The returned region has no pointers yet, `Gran` has to be pointed at the returned boundary.
+pub fn decode_region(input: &mut Decoder) -> io::Result<(TRegion, Vec<TGran>)> {
+    let mut region = TRegion {
+        x: input.f64()?,
+        y: input.f64()?,
+        R: input.f64()?,
+        TriW: input.bool()?,
+        CNu: input.f64()?,
+        PrMag: input.u8()?,
+        Px: input.f64()?,
+        Py: input.f64()?,
+        W: input.f64()?,
+        Ip: input.f64()?,
+        ST: input.f64()?,
+        PLOT: input.f64()?,
+        JbyIW: input.bool()?,
+        MatCharName: [0; 40],
+        KGran: 0,
+        Gran: null_mut(),
+        vGranMemo: 0,
+        NMatChar: 0
+    };
+    for byte in region.MatCharName.iter_mut() {
+        *byte = input.u8()?;
+    }
+    region.NMatChar = input.i32()?;
+    let mut gran = vec![];
+    for _ in 0..input.len()? {
+        gran.push(TGran { Node: input.i32()?, UgUzl: input.f64()? });
+    }
+    region.KGran = gran.len() as i32;
+    region.vGranMemo = gran.len() as i32;
+    Ok((region, gran))
+}
@@ -0,0 +1,135 @@
+use crate::model::borrow_types::TRegion;
+
+/// Names of the [RegionParams] fields, in the order of the region form.
+pub const REGION_FIELDS: [&str; 11] = ["R", "TriW", "PrMag", "MatCharName", "Mu", "Px", "Py", "W", "Ip", "Plot", "JbyIW"];
+
+/// Parameters of `FCreateRegion` besides the position of the region.
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, PartialEq)]
+#[serde(default)]
+pub struct RegionParams {
+    pub r: f64,
+    pub tri_w: bool,
+    pub pr_mag: u8,
+    pub mat_char_name: String,
+    pub mu: f64,
+    pub px: f64,
+    pub py: f64,
+    pub w: f64,
+    pub ip: f64,
+    pub plot: f64,
+    pub jby_iw: bool
+}
+
+impl Default for RegionParams {
+    /// Values FLib was always called with before the region form.
+    fn default() -> Self {
+        Self {
+            r: 20.,
+            tri_w: true,
+            pr_mag: 3,
+            mat_char_name: String::from("2013"),
+            mu: 1.,
+            px: 1.,
+            py: 1.,
+            w: 1.,
+            ip: 1.,
+            plot: 1.1,
+            jby_iw: true
+        }
+    }
+}
+
+impl RegionParams {
+    /// Form values, one per [REGION_FIELDS].
+    pub fn to_strings(&self) -> Vec<String> {
+        vec![
+            self.r.to_string(), self.tri_w.to_string(), self.pr_mag.to_string(), self.mat_char_name.clone(),
+            self.mu.to_string(), self.px.to_string(), self.py.to_string(), self.w.to_string(),
+            self.ip.to_string(), self.plot.to_string(), self.jby_iw.to_string()
+        ]
+    }
+
+    /// Reads the form values back and checks them with [RegionParams::validate].
+    pub fn parse(values: &[String]) -> Result<Self, String> {
+        if values.len() != REGION_FIELDS.len() {
+            return Err(format!("Region form has {} values instead of {}", values.len(), REGION_FIELDS.len()))
+        }
+        fn field<T: std::str::FromStr>(values: &[String], order: usize) -> Result<T, String> {
+            values[order].trim().parse::<T>()
+                .map_err(|_| format!("Failed to parse '{}' for {}", values[order], REGION_FIELDS[order]))
+        }
+        let params = Self {
+            r: field(values, 0)?,
+            tri_w: field(values, 1)?,
+            pr_mag: field(values, 2)?,
+            mat_char_name: values[3].trim().to_string(),
+            mu: field(values, 4)?,
+            px: field(values, 5)?,
+            py: field(values, 6)?,
+            w: field(values, 7)?,
+            ip: field(values, 8)?,
+            plot: field(values, 9)?,
+            jby_iw: field(values, 10)?
+        };
+        params.validate()?;
+        Ok(params)
+    }
+
+    /// Parameters of an existing region. `CNu` of 0 gives an infinite `mu`.
+    pub fn from_region(region: &TRegion) -> Self {
+        Self {
+            r: region.R,
+            tri_w: region.TriW,
+            pr_mag: region.PrMag,
+            mat_char_name: region.mat_char_name(),
+            mu: 1. / region.CNu,
+            px: region.Px,
+            py: region.Py,
+            w: region.W,
+            ip: region.Ip,
+            plot: region.PLOT,
+            jby_iw: region.JbyIW
+        }
+    }
+
+    /// Rejects values FLib can not mesh or solve with.
+    pub fn validate(&self) -> Result<(), String> {
+        let numbers = [("R", self.r), ("Mu", self.mu), ("Px", self.px), ("Py", self.py),
+            ("W", self.w), ("Ip", self.ip), ("Plot", self.plot)];
+        if let Some((name, _)) = numbers.iter().find(|(_, value)| !value.is_finite()) {
+            return Err(format!("{name} must be a finite number"))
+        }
+        if self.r <= 0. {
+            return Err(String::from("R must be positive"))
+        }
+        if self.mu <= 0. {
+            return Err(String::from("Mu must be positive"))
+        }
+        if self.mat_char_name.is_empty() {
+            return Err(String::from("MatCharName must not be empty"))
+        }
+        if !self.mat_char_name.is_ascii() || self.mat_char_name.len() > TRegion::MAT_CHAR_NAME_LEN {
+            return Err(format!("MatCharName must be at most {} ASCII characters", TRegion::MAT_CHAR_NAME_LEN))
+        }
+        Ok(())
+    }
+
+    /// Same as [RegionParams::validate], but only for the values that differ from `before`.
+    /// A region the core made with values out of the form's range, e.g. `CNu` of 0, stays editable.
+    pub fn validate_changed(&self, before: &Self) -> Result<(), String> {
+        let default = Self::default();
+        let pick = |new: f64, old: f64, fallback: f64| if new.to_bits() == old.to_bits() { fallback } else { new };
+        let changed = Self {
+            r: pick(self.r, before.r, default.r),
+            mat_char_name: if self.mat_char_name == before.mat_char_name { default.mat_char_name.clone() } else { self.mat_char_name.clone() },
+            mu: pick(self.mu, before.mu, default.mu),
+            px: pick(self.px, before.px, default.px),
+            py: pick(self.py, before.py, default.py),
+            w: pick(self.w, before.w, default.w),
+            ip: pick(self.ip, before.ip, default.ip),
+            plot: pick(self.plot, before.plot, default.plot),
+            ..default
+        };
+        changed.validate()
+    }
+}
@@ -1,13 +1,18 @@
-use std::rc::Rc;
+use std::sync::Arc;
 use iced::Point;
-use libloading::Library;
-use crate::app::undo_manager::UndoManager;
-use crate::foreign_functions::*;
+use crate::math_core::{MathCore, RegionParams};
 use super::borrow_types::*;
+use super::entity_ids::EntityIds;
+use super::own_model::shift_point_refs;
+use super::store::ModelStore;
 
-#[allow(unused_lifetimes)]
+/// Model in the memory of the math core.
+///
+/// The arrays of the core are only read here. Every change goes through a call of the core,
+/// after which the pointers and lengths are fetched again: the core may reallocate its arrays.
+/// An edit the core has no call for is refused.
 #[derive(Debug)]
-pub(super) struct BorrowModel {
+pub(crate) struct BorrowModel {
     points_ref: *const *mut TBPoint,
     points_len: i32,
     prims_ref: *const *mut TPrimitive,
@@ -17,19 +22,23 @@ pub(super) struct BorrowModel {
     elems_ref: *const *mut TElement,
     elems_len: i32,
     regions_ref: *const *mut TRegion,
+    regions_len: i32,
+    ids: EntityIds,
 
-    lib: Rc<Library>
+    core: Arc<dyn MathCore>
 }
 
 impl BorrowModel {
-    pub(super) fn new(lib: Rc<Library>, 
-                      points_ref: (*const *mut TBPoint, i32), 
-                      prims_ref: (*const *mut TPrimitive, i32), 
+    pub(super) fn new(core: Arc<dyn MathCore>,
+                      points_ref: (*const *mut TBPoint, i32),
+                      prims_ref: (*const *mut TPrimitive, i32),
                       nodes_ref: (*const *mut TNode, i32),
                       elems_ref: (*const *mut TElement, i32),
-                      regions_ref: *const *mut TRegion) -> Self {
+                      regions_ref: (*const *mut TRegion, i32),
+                      ids: EntityIds) -> Self {
         Self {
-            lib: lib.clone(),
+            core,
+            ids,
             points_ref: points_ref.0,
             points_len: points_ref.1,
             prims_ref: prims_ref.0,
@@ -38,265 +47,185 @@ impl BorrowModel {
             nodes_len: nodes_ref.1,
             elems_ref: elems_ref.0,
             elems_len: elems_ref.1,
-            regions_ref
+            regions_ref: regions_ref.0,
+            regions_len: regions_ref.1
         }
     }
-    pub(super) fn sync_points(&mut self) {
-        let points = get_points_ref(self.lib.clone());
-        self.points_len = points.1;
+    fn sync_points(&mut self) {
+        (self.points_ref, self.points_len) = self.core.points_ref();
     }
-    pub(super) fn sync_prims(&mut self) {
-        let prims = get_prims_ref(self.lib.clone());
-        //self.prims_ref = prims.0;
-        self.prims_len = prims.1;
+    fn sync_prims(&mut self) {
+        (self.prims_ref, self.prims_len) = self.core.prims_ref();
     }
-    pub(super) fn sync_nodes(&mut self) {
-        let nodes = get_nodes_ref(self.lib.clone());
-        //self.nodes_ref = nodes.0;
-        self.nodes_len = nodes.1;
+    fn sync_nodes(&mut self) {
+        (self.nodes_ref, self.nodes_len) = self.core.nodes_ref();
     }
-    pub(super) fn sync_elems(&mut self) {
-        let elems = get_elems_ref(self.lib.clone());
-        self.elems_len = elems.1;
+    fn sync_elems(&mut self) {
+        (self.elems_ref, self.elems_len) = self.core.elems_ref();
     }
-    
-    pub(super) fn sync_everything(&mut self) {
-        self.sync_points();
-        self.sync_prims();
-        self.sync_nodes();
-        self.sync_elems();
+    fn sync_regions(&mut self) {
+        (self.regions_ref, self.regions_len) = self.core.regions_ref();
     }
 }
 
-impl BorrowModel {
-    pub(super) fn tb_point_ref(&self, index: usize) -> Option<&mut TBPoint> {
-        if index < self.points_len as usize {
-            unsafe {
-                Some(
-                    &mut *(*self.points_ref).add(index)
-                )
-            }
-        } else {
-            None
-        }
+impl ModelStore for BorrowModel {
+    fn points(&self) -> &[TBPoint] {
+        unsafe { records(self.points_ref, self.points_len) }
     }
-    pub(super) fn get_point(&self, index: usize) -> Point {
-        assert!(index < self.points_len as usize);
-        unsafe {
-            let tb_point = (*self.points_ref).add(index);
-            Point::new((*tb_point).x as f32, (*tb_point).y as f32)
-        }
+    fn prims(&self) -> &[TPrimitive] {
+        unsafe { records(self.prims_ref, self.prims_len) }
     }
-    pub(super) fn get_point_r(&self, index: usize) -> f32 {
-        assert!(index < self.points_len as usize);
-        unsafe {
-            (*(*self.points_ref).add(index)).r as f32
-        }
+    fn nodes(&self) -> &[TNode] {
+        unsafe { records(self.nodes_ref, self.nodes_len) }
     }
-    pub(super) fn point_set(&mut self, num: usize, point: Point, point_r: f32) {
-        unsafe {
-            assert!(num < self.points_len as usize);
-            let points = std::slice::from_raw_parts_mut(*self.points_ref, self.points_len as usize);
-            f_set_point(self.lib.clone(), num as i32, &point);
-            points[num].r = point_r as f64;
+    fn node_links(&self, index: usize) -> &[i32] {
+        match self.nodes().get(index) {
+            Some(node) => unsafe { records(&node.NSW, node.KolSW) },
+            None => &[]
         }
     }
-    pub(super) fn points_len(&self) -> usize { self.points_len as usize }
-    pub(super) fn points_push(&mut self, point: Point, circle: f32) {
-        f_create_point(self.lib.clone(), (point, circle));
-        self.sync_points()
+    fn elems(&self) -> &[TElement] {
+        unsafe { records(self.elems_ref, self.elems_len) }
     }
-    pub(super) fn points_swap(&mut self, a: usize, b: usize) {
-        unsafe {
-            assert!(a < self.points_len as usize);
-            assert!(b < self.points_len as usize);
-            let points = std::slice::from_raw_parts_mut(*self.points_ref, self.points_len as usize);
-            points.swap(a, b);
-        }
+    fn regions(&self) -> &[TRegion] {
+        unsafe { records(self.regions_ref, self.regions_len) }
     }
-    pub(super) fn points_pop(&mut self) {
-        if self.points_len >= 1 {
-            f_del_point(self.lib.clone(), self.points_len - 1);
-            self.sync_points()
+    /// Filled by the core while meshing.
+    fn region_gran(&self, index: usize) -> &[TGran] {
+        match self.regions().get(index) {
+            Some(region) => unsafe { records(&region.Gran, region.KGran) },
+            None => &[]
         }
     }
-    
-    pub(super) fn t_primitive_ref(&self, index: usize) -> Option<&mut TPrimitive> {
-        if index < self.prims_len as usize {
-            unsafe {
-                Some(
-                    &mut *(*self.prims_ref).add(index)
-                )
-            }
-        } else {
-            None
-        }
+    fn field(&self, index: usize) -> Option<(f64, f64, f64)> {
+        (index < self.elems().len()).then(|| self.core.get_bx_by_bm(index as i32))
     }
-    pub(super) fn get_prim(&self, index: usize) -> &[i32; 3] {
-        assert!((index as i32) < self.prims_len);
-        unsafe { 
-            &(*(*self.prims_ref).add(index)).p
-        }
+    fn bm(&self, index: usize) -> Option<f32> {
+        (index < self.elems().len()).then(|| self.core.get_bm_only(index as i32))
     }
 
-    pub(super) fn prims_len(&self) -> usize { self.prims_len as usize }
+    fn ids(&self) -> &EntityIds { &self.ids }
+    fn ids_mut(&mut self) -> &mut EntityIds { &mut self.ids }
 
-    pub(super) fn prims_push(&mut self, prim: [i32; 3]) {
-        f_create_prim(self.lib.clone(), &prim);
-        self.sync_everything()
+    /// The core can always be asked, the field is zero until it is solved.
+    fn has_field(&self) -> bool {
+        self.has_mesh()
     }
-    pub(super) fn prims_insert(&mut self, index: usize, element: [i32; 3]) {
-        todo!();
-        //self.prims.insert(index, TPrimitive {p: element, ..Default::default()})
-        
+    fn is_borrowed(&self) -> bool {
+        true
     }
-    pub(super) fn prims_pop(&mut self) {
-        if self.prims_len >= 1 {
-            f_del_prim(self.lib.clone(), self.prims_len - 1);
-            self.sync_prims();
-        }
+    /// The core has no call to edit the records, only to move points.
+    fn editable_physics(&self) -> bool {
+        false
     }
 
-    pub(super) fn t_node_ref(&self, index: usize) -> Option<&mut TNode> {
-        if index < self.nodes_len as usize {
-            unsafe {
-                Some(
-                    &mut *(*self.nodes_ref).add(index)
-                )
-            }
-        } else {
-            None
+    /// The core only moves a point through `FSetXPoint`/`FSetYPoint`, the rest of it can not be edited.
+    fn commit_point(&mut self, index: usize, point: &TBPoint) -> Result<(), String> {
+        let current = self.points().get(index).cloned().ok_or_else(|| format!("No point {index}"))?;
+        if (point.r, point.TypPoint, point.Vp, point.Ip) != (current.r, current.TypPoint, current.Vp, current.Ip) {
+            return Err(format!("The core can only move point {index}, detach the model to edit the rest"))
+        }
+        if (point.x, point.y) != (current.x, current.y) {
+            self.core.set_point(index as i32, &Point::new(point.x as f32, point.y as f32));
+            self.sync_points()
         }
+        Ok(())
     }
-    pub(super) fn nodes_len(&self) -> usize {
-        self.nodes_len as usize
-    }
-    pub(super) fn node(&self, index: usize) -> Point {
-        assert!((index as i32) < self.nodes_len);
-        unsafe {
-            let t_node = &(*(*self.nodes_ref).add(index));
-            Point::new(t_node.x as f32, t_node.y as f32)
+    /// The core has no call to edit a prim.
+    fn commit_prim(&mut self, index: usize, prim: &TPrimitive) -> Result<(), String> {
+        let current = self.prims().get(index).ok_or_else(|| format!("No prim {index}"))?;
+        let fields = |prim: &TPrimitive| (prim.p, prim.TypPrim, prim.IsFront, prim.Vp, prim.Ip);
+        if fields(prim) != fields(current) {
+            return Err(format!("The core can not edit prim {index}, detach the model to edit it"))
         }
+        Ok(())
     }
-
-    pub(super) fn elems_len(&self) -> usize {
-        self.elems_len as usize
-    }
-    pub(super) fn elem(&self, index: usize) -> &[i32; 3] {
-        assert!((index as i32) < self.elems_len);
-        unsafe {
-            &(*(*self.elems_ref).add(index)).m
+    /// The core has no call to edit a region.
+    fn commit_region(&mut self, index: usize, region: &TRegion) -> Result<(), String> {
+        let current = self.regions().get(index).ok_or_else(|| format!("No region {index}"))?;
+        let params = |region: &TRegion| (region.x, region.y, region.ST, RegionParams::from_region(region));
+        if params(region) != params(current) {
+            return Err(format!("The core can not edit region {index}, detach the model to edit it"))
         }
+        Ok(())
     }
-    
-    pub(super) fn t_region_ref(&self, index: usize) -> Option<&mut TRegion> {
-        if index < self.elems_len as usize {
-            unsafe {
-                Some(
-                    &mut *(*self.regions_ref).add(index)
-                )
-            }
-        } else {
-            None
-        }
+    fn point_set(&mut self, index: usize, point: Point, point_r: f32) -> Result<(), String> {
+        let mut record = self.points().get(index).cloned().ok_or_else(|| format!("No point {index}"))?;
+        (record.x, record.y, record.r) = (point.x as f64, point.y as f64, point_r as f64);
+        self.commit_point(index, &record)
     }
-}
 
-impl BorrowModel {
-    pub(super) fn get_bm_only(&self, index: i32) -> f32 {
-        get_bm_only(self.lib.clone(), index)
-    }
-}
-
-impl BorrowModel {
-    pub(super) fn clear(&mut self) {
-        f_del_memo_model(self.lib.clone());
-        self.points_len = 0;
-        self.prims_len = 0;
-        self.nodes_len = 0;
-        self.elems_len = 0;
-    }
-    pub(super) fn find_point(&self, point: Point, scale: f32, zoom_scale: f32) -> usize {
-        if self.points_len == 0 {
-            return 0;
+    /// The core only appends points and takes nothing of them but the position and `r`.
+    fn points_insert(&mut self, index: usize, record: TBPoint) -> Result<(), String> {
+        let len = self.points().len();
+        if index != len {
+            return Err(format!("The core only appends points, point {index} can not be put before the last one"))
         }
-        unsafe {
-            let points = std::slice::from_raw_parts(*self.points_ref, self.points_len as usize);
-            points
-                .iter()
-                .position(|big_point| { Point::new(big_point.x as f32, big_point.y as f32).distance(point) < scale / zoom_scale * 2.0 })
-                .unwrap_or(self.points_len as usize)
+        let default = TBPoint::default();
+        if (record.TypPoint, record.Vp, record.Ip) != (default.TypPoint, default.Vp, default.Ip) {
+            return Err(format!("The core can not create point {index} with its TypPoint, Vp and Ip"))
         }
+        self.core.create_point(Point::new(record.x as f32, record.y as f32), record.r as f32);
+        self.sync_points();
+        if self.points().len() != len + 1 {
+            return Err(format!("The core has not created point {index}"))
+        }
+        Ok(())
     }
-    pub(super) fn find_min_max(&self) -> (Point, Point) {
-        if self.points_len == 0 {
-            return (Point::new(0., 0.), Point::new(1000., 1000.))
+    /// The core has to move the point indices of the prims itself, like [super::own_model::OwnModel] does.
+    fn points_remove(&mut self, index: usize) -> Result<(), String> {
+        if index >= self.points().len() {
+            return Err(format!("No point {index}"))
         }
-        unsafe {
-            let points = std::slice::from_raw_parts(*self.points_ref, self.points_len as usize);
-            let mut min = Point::new(points[0].x, points[0].y);
-            let mut max = min;
-
-            for point in points.iter() {
-                min.x = min.x.min(point.x);
-                min.y = min.y.min(point.y);
-                max.x = max.x.max(point.x);
-                max.y = max.y.max(point.y);
-            }
-            let min = Point::new(min.x as f32, min.y as f32);
-            let max = Point::new(max.x as f32, max.y as f32);
-            (min, max)
+        let mut expected = self.prims().to_vec();
+        expected.iter_mut().for_each(|prim| shift_point_refs(&mut prim.p, index as i32 + 1, -1));
+        if !self.core.del_point(index as i32) {
+            return Err(format!("The core has not deleted point {index}"))
         }
+        self.sync_points();
+        self.sync_prims();
+        if self.prims().iter().zip(&expected).any(|(prim, expected)| prim.p != expected.p) {
+            return Err(format!("The core has not moved the point indices of the prims after point {index}"))
+        }
+        Ok(())
     }
-    pub(crate) fn replace_prim(&mut self, one: i32, two: i32) {
-        if self.prims_len == 0 {
-            return;
+    /// The core only appends prims and takes nothing of them but the points, `TypPrim` and `Vp`.
+    fn prims_insert(&mut self, index: usize, record: TPrimitive) -> Result<(), String> {
+        let len = self.prims().len();
+        if index != len {
+            return Err(format!("The core only appends prims, prim {index} can not be put before the last one"))
         }
-        unsafe{
-            let prims = std::slice::from_raw_parts_mut(*self.prims_ref, self.prims_len as usize);
-            prims.iter_mut().for_each(|prim| {
-                if prim.p[0] == one {
-                    prim.p[0] = two
-                } else if prim.p[0] == two {
-                    prim.p[0] = one
-                }
-
-                if prim.p[1] == one {
-                    prim.p[1] = two
-                } else if prim.p[1] == two {
-                    prim.p[1] = one
-                }
-
-                if prim.p[2] == one {
-                    prim.p[2] = two
-                } else if prim.p[2] == two {
-                    prim.p[2] = one
-                }
-            })
+        let default = TPrimitive::default();
+        if (record.IsFront, record.Ip) != (default.IsFront, default.Ip) {
+            return Err(format!("The core can not create prim {index} with its IsFront and Ip"))
         }
+        self.core.create_prim(&record.p, record.TypPrim, record.Vp);
+        self.sync_prims();
+        if self.prims().len() != len + 1 {
+            return Err(format!("The core has not created prim {index}"))
+        }
+        Ok(())
     }
-    pub(super) fn prims_retain_safe<F>(&mut self, mut f: F, journal: &mut UndoManager)
-    where
-        F: FnMut(&[i32; 3]) -> bool
-    {
-        if self.prims_len == 0 {
-            return;
+    fn prims_remove(&mut self, index: usize) -> Result<(), String> {
+        if index >= self.prims().len() {
+            return Err(format!("No prim {index}"))
         }
-        unsafe {
-            let mut prims = Vec::from_raw_parts(*self.prims_ref, self.prims_len as usize, self.prims_len as usize);
-            prims
-                .iter()
-                .enumerate()
-                .rev()
-                .for_each(|(placement, x)| {
-                    if !f(&x.p) {
-                        journal.deleted_prim(placement, x.p.clone())
-                    }
-                });
-            prims.retain(|x| {
-                f(&x.p)
-            });
-            self.prims_len = prims.len() as i32;
+        if !self.core.del_prim(index as i32) {
+            return Err(format!("The core has not deleted prim {index}"))
         }
+        self.sync_prims();
+        Ok(())
+    }
+
+    fn clear(&mut self) {
+        self.core.del_memo_model();
+        self.sync()
+    }
+    fn sync(&mut self) {
+        self.sync_points();
+        self.sync_prims();
+        self.sync_nodes();
+        self.sync_elems();
+        self.sync_regions();
     }
 }
@@ -12,7 +12,7 @@ pub(crate) struct TBPoint {
 }
 
 #[allow(non_snake_case, dead_code)]
-#[derive(Debug, Default)]
+#[derive(Clone, Debug)]
 #[repr(C)]
 pub(crate) struct TPrimitive {
     pub p: [i32; 3],
@@ -33,17 +33,17 @@ pub(crate) struct TNode {
     pub PNode: i32,
     pub KolSW: i32,
     pub NSW: *mut i32, //array of integer <- это
-    KolSI: i32,
-    NSI: *mut i32, //array of integer
-    NSK: *mut i8, //array of shortint; 
-    vKolSWMemo: i32,
-    vKolSIMemo: i32,
+    pub KolSI: i32,
+    pub NSI: *mut i32, //array of integer
+    pub NSK: *mut i8, //array of shortint; 
+    pub vKolSWMemo: i32,
+    pub vKolSIMemo: i32,
     pub F: f64,
     pub Yp: f64
 }
 
 #[allow(non_snake_case, dead_code)]
-#[derive(Debug)]
+#[derive(Clone, Debug, Default)]
 #[repr(C)]
 pub(crate) struct TElement {
     pub m: [i32; 3],
@@ -63,7 +63,7 @@ pub(crate) struct TElement {
 }
 
 #[allow(non_snake_case, dead_code)]
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 #[repr(C)]
 pub(crate) struct TRegion {
     pub x: f64, 
@@ -76,21 +76,99 @@ pub(crate) struct TRegion {
     pub Py: f64, //
     pub W: f64, //
     pub Ip: f64, //
-    ST: f64,
-    PLOT: f64,
-    JbyIW: bool,
+    pub ST: f64,
+    pub PLOT: f64,
+    pub JbyIW: bool,
     pub MatCharName: [u8; 40], // 
     //------------------------------
-    KGran: i32,
-    Gran: *mut TGran,
-    vGranMemo: i32,
-    NMatChar: i32,
+    pub KGran: i32,
+    pub Gran: *mut TGran,
+    pub vGranMemo: i32,
+    pub NMatChar: i32,
+}
+
+impl Default for TPrimitive {
+    /// Prim with the condition FLib always got for new prims: `TypPrim = 1`, `Vp = 1`.
+    fn default() -> Self {
+        Self { p: [-1; 3], TypPrim: 1, IsFront: false, Vp: 1., Ip: 0. }
+    }
+}
+
+impl Default for TNode {
+    /// Node without links, the arrays are null.
+    fn default() -> Self {
+        Self {
+            x: 0., y: 0., VP: 0., TypNode: 0, PNode: 0,
+            KolSW: 0, NSW: std::ptr::null_mut(),
+            KolSI: 0, NSI: std::ptr::null_mut(), NSK: std::ptr::null_mut(),
+            vKolSWMemo: 0, vKolSIMemo: 0, F: 0., Yp: 0.
+        }
+    }
+}
+
+impl TNode {
+    /// Copy without the arrays of the core: `NSW`, `NSI` and `NSK` are null.
+    pub fn detached(&self) -> Self {
+        Self {
+            NSW: std::ptr::null_mut(),
+            NSI: std::ptr::null_mut(),
+            NSK: std::ptr::null_mut(),
+            ..*self
+        }
+    }
+}
+
+impl TRegion {
+    /// Copy without the boundary of the core: `Gran` is null.
+    pub fn detached(&self) -> Self {
+        Self { Gran: std::ptr::null_mut(), ..self.clone() }
+    }
+
+    /// `MatCharName` is a Pascal `string[39]`: the length byte, then the characters.
+    pub const MAT_CHAR_NAME_LEN: usize = 39;
+
+    pub fn mat_char_name(&self) -> String {
+        read_mat_char_name(&self.MatCharName)
+    }
+
+    /// Longer names are cut to [TRegion::MAT_CHAR_NAME_LEN] bytes.
+    pub fn set_mat_char_name(&mut self, name: &str) {
+        self.MatCharName = write_mat_char_name(name)
+    }
+}
+
+/// Text of a `MatCharName`, see [TRegion::mat_char_name].
+pub fn read_mat_char_name(bytes: &[u8; 40]) -> String {
+    let len = (bytes[0] as usize).min(TRegion::MAT_CHAR_NAME_LEN);
+    String::from_utf8_lossy(&bytes[1..=len]).into_owned()
+}
+
+/// `MatCharName` with the text, see [TRegion::set_mat_char_name].
+pub fn write_mat_char_name(name: &str) -> [u8; 40] {
+    let bytes = &name.as_bytes()[..name.len().min(TRegion::MAT_CHAR_NAME_LEN)];
+    let mut result = [0; 40];
+    result[0] = bytes.len() as u8;
+    result[1..=bytes.len()].copy_from_slice(bytes);
+    result
 }
 
 #[allow(non_snake_case, dead_code)]
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 #[repr(C)]
 pub(crate) struct TGran {
-    Node: i32,
-    UgUzl: f64
+    pub Node: i32,
+    pub UgUzl: f64
+}
+
+/// Slice behind the array variable `array` of the core, empty for null pointers.
+///
+/// # Safety
+/// `array` has to point at an array of at least `len` records, which is not changed
+/// while the slice is alive.
+pub(crate) unsafe fn records<'a, T>(array: *const *mut T, len: i32) -> &'a [T] {
+    if array.is_null() || (*array).is_null() || len <= 0 {
+        &[]
+    } else {
+        std::slice::from_raw_parts(*array, len as usize)
+    }
 }
@@ -0,0 +1,28 @@
+use iced::Color;
+use super::kinds::byte_enum;
+
+byte_enum! {
+    /// Boundary condition of a prim. The kind is kept in `TPrimitive::TypPrim`, the value in `TPrimitive::Vp`.
+    ///
+    /// Only code 1 is known from FLib: the editor sent `TypPrim = 1, Vp = 1` for every prim before conditions could be chosen,
+    /// and new prims still get it. Other codes are shown as they are.
+    #[derive(Default)]
+    Boundary {
+        /// A = value along the prim.
+        #[default]
+        Dirichlet = 1 => "Dirichlet (A = const)"
+    }
+}
+
+impl Boundary {
+    /// `Vp` of a new prim, together with the default condition.
+    pub const DEFAULT_VP: f64 = 1.0;
+
+    /// Color of the prims with this condition. `None` keeps the "Prims" color.
+    pub fn color(self) -> Option<Color> {
+        match self {
+            Self::Dirichlet => Some(Color::from_rgb8(0, 96, 224)),
+            Self::Unknown(_) => None
+        }
+    }
+}
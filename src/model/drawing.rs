@@ -33,19 +33,19 @@ impl Drawing {
             Self::LinePoint { point, num } => {
                 let cursor_pos = cursor.position_in(bounds).unwrap_or(point);
                 Self::draw_point(&mut frame, zoom, scale, model, point, num);
-                if num.is_none() || num.unwrap() >= model.points_len() {
-                    frame.stroke(&Path::line(zoom.apply(point), cursor_pos),
-                                 Stroke::default().with_width(2.0 * scale).with_color(Color::from_rgb8(255, 0, 0)));
-                }
-                else {
-                    frame.stroke(&Path::line(zoom.apply(model.points(num.unwrap())), cursor_pos),
-                                 Stroke::default().with_width(scale).with_color(Color::from_rgb8(255, 0, 0)));
+                match num {
+                    Some(num) if num < model.points_len() => {
+                        frame.stroke(&Path::line(zoom.apply(model.points(num)), cursor_pos),
+                                     Stroke::default().with_width(scale).with_color(Color::from_rgb8(255, 0, 0)));
+                    }
+                    _ => {
+                        frame.stroke(&Path::line(zoom.apply(point), cursor_pos),
+                                     Stroke::default().with_width(2.0 * scale).with_color(Color::from_rgb8(255, 0, 0)));
+                    }
                 }
             }
-            Self::SelectPoint { point, num } => {
-                if num < model.points_len() {
-                    Self::draw_point(&mut frame, zoom, scale, model, point, Some(num));
-                }
+            Self::SelectPoint { point, num } if num < model.points_len() => {
+                Self::draw_point(&mut frame, zoom, scale, model, point, Some(num));
             }
             Self::ArcPoint { point, num} => {
                 Self::draw_point(&mut frame, zoom, scale, model, point, num)
@@ -0,0 +1,493 @@
+//! DXF import and export of the geometry.
+//!
+//! LINE, ARC, CIRCLE, LWPOLYLINE and POINT entities become points and prims: a line is `[a, b, -1]`,
+//! an arc is `[start, end, center]` going counterclockwise, a circle is two arcs.
+//! ARC, CIRCLE and LWPOLYLINE are taken from their object coordinate system, see [Ocs].
+//! Endpoints closer than [MERGE_TOLERANCE] of the drawing size become one point, so connected entities stay connected.
+//! The export writes the prims, points, region markers and the mesh on their own layers;
+//! the region and mesh layers are skipped on import. DXF has no place for the boundary conditions
+//! (`TypPrim`, `Vp`) or the materials, they are lost with a message.
+
+use std::collections::HashMap;
+use std::fmt::{Display, Write};
+use super::borrow_types::{TBPoint, TPrimitive};
+use super::own_model::OwnModel;
+use super::validate::{elem_fits, prim_fits};
+use super::Model;
+
+pub const DXF_EXTENSION: &str = "dxf";
+
+pub const LAYER_PRIMS: &str = "PRIMS";
+pub const LAYER_POINTS: &str = "POINTS";
+pub const LAYER_REGIONS: &str = "REGIONS";
+pub const LAYER_MESH: &str = "MESH";
+
+/// Part of the larger side of the drawing within which two endpoints are merged.
+pub const MERGE_TOLERANCE: f64 = 1e-6;
+
+/// The path ends with [DXF_EXTENSION].
+pub fn is_dxf(path: &str) -> bool {
+    path.trim().rsplit_once('.').is_some_and(|(_, extension)| extension.eq_ignore_ascii_case(DXF_EXTENSION))
+}
+
+/// One entity of the ENTITIES section with its group codes.
+struct DxfEntity {
+    kind: String,
+    /// Line of the entity name, for the messages.
+    line: usize,
+    groups: Vec<(i32, String)>
+}
+
+impl DxfEntity {
+    fn layer(&self) -> &str {
+        self.groups.iter().find(|(code, _)| *code == 8).map_or("0", |(_, value)| value.as_str())
+    }
+
+    fn number(&self, code: i32) -> Result<f64, String> {
+        let (_, value) = self.groups.iter()
+            .find(|(group, _)| *group == code)
+            .ok_or_else(|| format!("line {}: {} has no group {code}", self.line, self.kind))?;
+        value.parse().map_err(|_| format!("line {}: group {code} of {} is not a number: {value}", self.line, self.kind))
+    }
+
+    fn number_or(&self, code: i32, default: f64) -> Result<f64, String> {
+        if self.groups.iter().any(|(group, _)| *group == code) { self.number(code) } else { Ok(default) }
+    }
+}
+
+/// Geometry of an entity in model coordinates.
+enum Shape {
+    Point(f64, f64),
+    Line((f64, f64), (f64, f64)),
+    /// Counterclockwise from `start` to `end`.
+    Arc { center: (f64, f64), start: (f64, f64), end: (f64, f64) },
+    Circle { center: (f64, f64), radius: f64 }
+}
+
+impl Shape {
+    /// Exact positions of the file are merged first, positions computed from angles snap to them.
+    fn merge_order(&self) -> u8 {
+        match self {
+            Self::Point(..) | Self::Line(..) => 0,
+            Self::Arc { .. } => 1,
+            Self::Circle { .. } => 2
+        }
+    }
+}
+
+/// Object coordinate system of an entity, from its extrusion direction (groups 210, 220 and 230)
+/// by the arbitrary axis algorithm of DXF. A drawing mirrored in CAD has the extrusion `(0, 0, -1)`,
+/// which flips x and the direction of arcs. A tilted plane is projected onto the model plane.
+struct Ocs {
+    x: [f64; 3],
+    y: [f64; 3],
+    normal: [f64; 3],
+    /// Height of the entity along `normal`.
+    elevation: f64
+}
+
+impl Ocs {
+    fn of(entity: &DxfEntity) -> Result<Self, String> {
+        fn cross(a: [f64; 3], b: [f64; 3]) -> [f64; 3] {
+            [a[1] * b[2] - a[2] * b[1], a[2] * b[0] - a[0] * b[2], a[0] * b[1] - a[1] * b[0]]
+        }
+        fn unit(v: [f64; 3]) -> [f64; 3] {
+            let len = (v[0] * v[0] + v[1] * v[1] + v[2] * v[2]).sqrt();
+            if len > 0. { [v[0] / len, v[1] / len, v[2] / len] } else { [0., 0., 1.] }
+        }
+        let normal = unit([entity.number_or(210, 0.)?, entity.number_or(220, 0.)?, entity.number_or(230, 1.)?]);
+        let x = if normal[0].abs() < 1. / 64. && normal[1].abs() < 1. / 64. {
+            unit(cross([0., 1., 0.], normal))
+        } else {
+            unit(cross([0., 0., 1.], normal))
+        };
+        let elevation = entity.number_or(if entity.kind == "LWPOLYLINE" { 38 } else { 30 }, 0.)?;
+        Ok(Self { x, y: unit(cross(normal, x)), normal, elevation })
+    }
+
+    fn point(&self, (x, y): (f64, f64)) -> (f64, f64) {
+        (x * self.x[0] + y * self.y[0] + self.elevation * self.normal[0], x * self.x[1] + y * self.y[1] + self.elevation * self.normal[1])
+    }
+
+    /// The shape in model coordinates, an arc seen from below goes the other way.
+    fn shape(&self, shape: Shape) -> Shape {
+        match shape {
+            Shape::Point(x, y) => {
+                let (x, y) = self.point((x, y));
+                Shape::Point(x, y)
+            }
+            Shape::Line(from, to) => Shape::Line(self.point(from), self.point(to)),
+            Shape::Arc { center, start, end } if self.normal[2] < 0. => Shape::Arc { center: self.point(center), start: self.point(end), end: self.point(start) },
+            Shape::Arc { center, start, end } => Shape::Arc { center: self.point(center), start: self.point(start), end: self.point(end) },
+            Shape::Circle { center, radius } => Shape::Circle { center: self.point(center), radius }
+        }
+    }
+}
+
+/// The pairs of group code and value of the ENTITIES section, split into entities.
+fn read_entities(text: &str) -> Result<Vec<DxfEntity>, String> {
+    let lines: Vec<&str> = text.lines().collect();
+    let mut entities = vec![];
+    let mut in_entities = false;
+    let mut section_name_next = false;
+    for (pair, chunk) in lines.chunks(2).enumerate() {
+        let line = pair * 2 + 1;
+        let [code, value] = chunk else {
+            if chunk.iter().all(|rest| rest.trim().is_empty()) {
+                break
+            }
+            return Err(format!("line {line}: group code without a value"))
+        };
+        let code: i32 = code.trim().parse().map_err(|_| format!("line {line}: group code {} is not a number", code.trim()))?;
+        let value = value.trim();
+
+        if section_name_next {
+            section_name_next = false;
+            in_entities = code == 2 && value == "ENTITIES";
+            continue
+        }
+        if code == 0 {
+            match value {
+                "SECTION" => section_name_next = true,
+                "ENDSEC" => in_entities = false,
+                "EOF" => break,
+                kind if in_entities => entities.push(DxfEntity { kind: kind.to_string(), line: line + 1, groups: vec![] }),
+                _ => {}
+            }
+        } else if in_entities {
+            if let Some(entity) = entities.last_mut() {
+                entity.groups.push((code, value.to_string()))
+            }
+        }
+    }
+    Ok(entities)
+}
+
+/// Shapes of a supported entity, `None` for other kinds.
+fn shapes_of(entity: &DxfEntity) -> Result<Option<Vec<Shape>>, String> {
+    let shapes = match entity.kind.as_str() {
+        "POINT" => vec![Shape::Point(entity.number(10)?, entity.number(20)?)],
+        "LINE" => vec![Shape::Line((entity.number(10)?, entity.number(20)?), (entity.number(11)?, entity.number(21)?))],
+        "CIRCLE" => vec![Shape::Circle { center: (entity.number(10)?, entity.number(20)?), radius: entity.number(40)? }],
+        "ARC" => {
+            let (center, radius) = ((entity.number(10)?, entity.number(20)?), entity.number(40)?);
+            let (start, end) = (entity.number(50)?, entity.number(51)?);
+            if (end - start).rem_euclid(360.) == 0. {
+                vec![Shape::Circle { center, radius }]
+            } else {
+                let at = |degrees: f64| (center.0 + radius * degrees.to_radians().cos(), center.1 + radius * degrees.to_radians().sin());
+                vec![Shape::Arc { center, start: at(start), end: at(end) }]
+            }
+        }
+        "LWPOLYLINE" => polyline_shapes(entity)?,
+        _ => return Ok(None)
+    };
+    if matches!(entity.kind.as_str(), "CIRCLE" | "ARC" | "LWPOLYLINE") {
+        let ocs = Ocs::of(entity)?;
+        return Ok(Some(shapes.into_iter().map(|shape| ocs.shape(shape)).collect()))
+    }
+    Ok(Some(shapes))
+}
+
+/// Segments of a LWPOLYLINE, a segment with a bulge is an arc.
+fn polyline_shapes(entity: &DxfEntity) -> Result<Vec<Shape>, String> {
+    let mut vertices: Vec<((f64, f64), f64)> = vec![];
+    let mut x = None;
+    for (code, value) in &entity.groups {
+        let number = || value.parse::<f64>().map_err(|_| format!("line {}: group {code} of LWPOLYLINE is not a number: {value}", entity.line));
+        match code {
+            10 => x = Some(number()?),
+            20 => vertices.push(((x.take().unwrap_or_default(), number()?), 0.)),
+            42 => if let Some(vertex) = vertices.last_mut() {
+                vertex.1 = number()?
+            },
+            _ => {}
+        }
+    }
+    let closed = entity.number_or(70, 0.)? as i32 & 1 == 1;
+    let segments = if closed { vertices.len() } else { vertices.len().saturating_sub(1) };
+
+    let mut shapes = vec![];
+    for i in 0..segments {
+        let (from, bulge) = vertices[i];
+        let (to, _) = vertices[(i + 1) % vertices.len()];
+        if bulge.abs() < 1e-12 {
+            shapes.push(Shape::Line(from, to));
+            continue
+        }
+        // The bulge is the tangent of a quarter of the included angle, negative for a clockwise arc.
+        let angle = 4. * bulge.atan();
+        let (dx, dy) = (to.0 - from.0, to.1 - from.1);
+        let chord = dx.hypot(dy);
+        if chord == 0. {
+            continue
+        }
+        let offset = chord / (2. * (angle / 2.).tan());
+        let center = ((from.0 + to.0) / 2. - dy / chord * offset, (from.1 + to.1) / 2. + dx / chord * offset);
+        let (start, end) = if angle > 0. { (from, to) } else { (to, from) };
+        shapes.push(Shape::Arc { center, start, end })
+    }
+    Ok(shapes)
+}
+
+/// Points of the import, each new position is checked against the merged ones nearby.
+struct PointMerger {
+    tolerance: f64,
+    circle: f64,
+    cells: HashMap<(i64, i64), Vec<usize>>,
+    points: Vec<TBPoint>
+}
+
+impl PointMerger {
+    fn index(&mut self, (x, y): (f64, f64)) -> i32 {
+        let cell = ((x / self.tolerance).floor() as i64, (y / self.tolerance).floor() as i64);
+        for dx in -1..=1 {
+            for dy in -1..=1 {
+                let mut near = self.cells.get(&(cell.0 + dx, cell.1 + dy)).into_iter().flatten();
+                if let Some(&index) = near.find(|&&index| {
+                    let point = &self.points[index];
+                    (point.x - x).hypot(point.y - y) <= self.tolerance
+                }) {
+                    return index as i32
+                }
+            }
+        }
+        self.points.push(TBPoint { x, y, r: self.circle, ..Default::default() });
+        self.cells.entry(cell).or_default().push(self.points.len() - 1);
+        self.points.len() as i32 - 1
+    }
+}
+
+/// Reads the geometry into an own model, new points get the `circle`.
+pub fn open_dxf(path: &str, circle: f32) -> Result<Model, String> {
+    let text = std::fs::read_to_string(path.trim()).map_err(|error| format!("{}: {error}", path.trim()))?;
+    let (model, skipped) = read_dxf(&text, circle).map_err(|error| format!("{}: {error}", path.trim()))?;
+    for (kind, count) in skipped {
+        println!("{}: {count} {kind} entities are not supported and skipped", path.trim())
+    }
+    Ok(model)
+}
+
+/// The model of the DXF `text` and the count of every kind of skipped entities.
+fn read_dxf(text: &str, circle: f32) -> Result<(Model, HashMap<String, usize>), String> {
+    let entities = read_entities(text)?;
+
+    let mut shapes = vec![];
+    let mut skipped: HashMap<String, usize> = HashMap::new();
+    for entity in entities.iter().filter(|entity| ![LAYER_REGIONS, LAYER_MESH].contains(&entity.layer())) {
+        match shapes_of(entity)? {
+            Some(entity_shapes) => shapes.extend(entity_shapes),
+            None => *skipped.entry(entity.kind.clone()).or_default() += 1
+        }
+    }
+
+    let (mut min, mut max) = ((f64::MAX, f64::MAX), (f64::MIN, f64::MIN));
+    let mut extend = |(x, y): (f64, f64), reach: f64| {
+        (min.0, min.1) = (min.0.min(x - reach), min.1.min(y - reach));
+        (max.0, max.1) = (max.0.max(x + reach), max.1.max(y + reach));
+    };
+    for shape in &shapes {
+        match *shape {
+            Shape::Point(x, y) => extend((x, y), 0.),
+            Shape::Line(from, to) => {
+                extend(from, 0.);
+                extend(to, 0.)
+            }
+            Shape::Arc { center, start, .. } => extend(center, (start.0 - center.0).hypot(start.1 - center.1)),
+            Shape::Circle { center, radius } => extend(center, radius)
+        }
+    }
+    let size = (max.0 - min.0).max(max.1 - min.1);
+    let tolerance = if size.is_finite() && size > 0. { size * MERGE_TOLERANCE } else { MERGE_TOLERANCE };
+
+    shapes.sort_by_key(Shape::merge_order);
+    let mut merger = PointMerger { tolerance, circle: circle as f64, cells: HashMap::new(), points: vec![] };
+    let mut prims = vec![];
+    for shape in shapes {
+        match shape {
+            Shape::Point(x, y) => {
+                merger.index((x, y));
+            }
+            Shape::Line(from, to) => {
+                let (a, b) = (merger.index(from), merger.index(to));
+                if a != b {
+                    prims.push([a, b, -1])
+                }
+            }
+            Shape::Arc { center, start, end } => {
+                let (a, b, c) = (merger.index(start), merger.index(end), merger.index(center));
+                if a != b && a != c && b != c {
+                    prims.push([a, b, c])
+                }
+            }
+            // A full circle would start and end in one point, it is split in halves.
+            Shape::Circle { center, radius } => {
+                let (a, b) = (merger.index((center.0 + radius, center.1)), merger.index((center.0 - radius, center.1)));
+                let c = merger.index(center);
+                if a != b && a != c {
+                    prims.push([a, b, c]);
+                    prims.push([b, a, c])
+                }
+            }
+        }
+    }
+
+    let own = OwnModel {
+        points: merger.points,
+        prims: prims.into_iter().map(|p| TPrimitive { p, ..Default::default() }).collect(),
+        ..OwnModel::default()
+    };
+    Ok((Model::from_store(Box::new(own)), skipped))
+}
+
+/// Writes the group code and its value.
+fn group(out: &mut String, code: i32, value: impl Display) {
+    let _ = write!(out, "{code:>3}\n{value}\n");
+}
+
+fn entity(out: &mut String, kind: &str, layer: &str) {
+    group(out, 0, kind);
+    group(out, 8, layer);
+}
+
+/// Prims and points, region markers and the mesh, each on its own layer.
+pub fn save_dxf(path: &str, model: &Model) -> Result<(), String> {
+    std::fs::write(path.trim(), write_dxf(model)).map_err(|error| format!("{}: {error}", path.trim()))?;
+    if model.prims_len() > 0 {
+        println!("{}: the boundary conditions (TypPrim, Vp) of the prims are not written to DXF, save a .vrp project to keep them", path.trim())
+    }
+    Ok(())
+}
+
+fn write_dxf(model: &Model) -> String {
+    let mut out = String::new();
+    group(&mut out, 0, "SECTION");
+    group(&mut out, 2, "TABLES");
+    group(&mut out, 0, "TABLE");
+    group(&mut out, 2, "LAYER");
+    group(&mut out, 70, 4);
+    for (layer, color) in [(LAYER_PRIMS, 7), (LAYER_POINTS, 3), (LAYER_REGIONS, 1), (LAYER_MESH, 8)] {
+        group(&mut out, 0, "LAYER");
+        group(&mut out, 2, layer);
+        group(&mut out, 70, 0);
+        group(&mut out, 62, color);
+        group(&mut out, 6, "CONTINUOUS");
+    }
+    group(&mut out, 0, "ENDTAB");
+    group(&mut out, 0, "ENDSEC");
+
+    group(&mut out, 0, "SECTION");
+    group(&mut out, 2, "ENTITIES");
+    let point = |index: i32| model.tb_point_ref(index as usize).map_or((0., 0.), |point| (point.x, point.y));
+    for index in 0..model.points_len() {
+        let (x, y) = point(index as i32);
+        entity(&mut out, "POINT", LAYER_POINTS);
+        group(&mut out, 10, x);
+        group(&mut out, 20, y);
+    }
+    for index in 0..model.prims_len() {
+        let prim = model.prims(index);
+        if !prim_fits(prim, model.points_len()) {
+            continue
+        }
+        let (start, end) = (point(prim[0]), point(prim[1]));
+        if prim[2] == -1 {
+            entity(&mut out, "LINE", LAYER_PRIMS);
+            group(&mut out, 10, start.0);
+            group(&mut out, 20, start.1);
+            group(&mut out, 11, end.0);
+            group(&mut out, 21, end.1);
+        } else {
+            let center = point(prim[2]);
+            let angle = |(x, y): (f64, f64)| (y - center.1).atan2(x - center.0).to_degrees().rem_euclid(360.);
+            entity(&mut out, "ARC", LAYER_PRIMS);
+            group(&mut out, 10, center.0);
+            group(&mut out, 20, center.1);
+            group(&mut out, 40, (start.0 - center.0).hypot(start.1 - center.1));
+            group(&mut out, 50, angle(start));
+            group(&mut out, 51, angle(end));
+        }
+    }
+    for index in 0..model.regions_len() {
+        let Some(region) = model.t_region_ref(index) else {
+            continue
+        };
+        entity(&mut out, "POINT", LAYER_REGIONS);
+        group(&mut out, 10, region.x);
+        group(&mut out, 20, region.y);
+        if region.R > 0. {
+            entity(&mut out, "CIRCLE", LAYER_REGIONS);
+            group(&mut out, 10, region.x);
+            group(&mut out, 20, region.y);
+            group(&mut out, 40, region.R);
+        }
+    }
+    for index in 0..model.elems_len() {
+        let elem = model.elems(index);
+        if !elem_fits(elem, model.nodes_len()) {
+            continue
+        }
+        entity(&mut out, "LWPOLYLINE", LAYER_MESH);
+        group(&mut out, 90, 3);
+        group(&mut out, 70, 1);
+        for &node in elem {
+            let node = model.t_node_ref(node as usize).map_or((0., 0.), |node| (node.x, node.y));
+            group(&mut out, 10, node.0);
+            group(&mut out, 20, node.1);
+        }
+    }
+    group(&mut out, 0, "ENDSEC");
+    group(&mut out, 0, "EOF");
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Rounded positions of the points of every prim, sorted, so the order of the records does not matter.
+    fn prim_positions(model: &Model) -> Vec<Vec<(f64, f64)>> {
+        let mut prims: Vec<_> = (0..model.prims_len()).map(|index| {
+            model.prims(index).iter()
+                .filter_map(|&point| usize::try_from(point).ok().and_then(|point| model.tb_point_ref(point)))
+                .map(|point| ((point.x * 1e9).round() / 1e9, (point.y * 1e9).round() / 1e9))
+                .collect::<Vec<_>>()
+        }).collect();
+        prims.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        prims
+    }
+
+    #[test]
+    fn close_endpoints_are_merged() {
+        let text = "0\nSECTION\n2\nENTITIES\n\
+            0\nLINE\n8\n0\n10\n0\n20\n0\n11\n10\n21\n0\n\
+            0\nLINE\n8\n0\n10\n10.000000001\n20\n0\n11\n10\n21\n10\n\
+            0\nARC\n8\n0\n10\n10\n20\n0\n40\n10\n50\n90\n51\n180\n\
+            0\nSPLINE\n8\n0\n\
+            0\nENDSEC\n0\nEOF\n";
+        let (model, skipped) = read_dxf(text, 1.).unwrap();
+        assert_eq!((model.points_len(), model.prims_len()), (3, 3));
+        assert_eq!(prim_positions(&model), vec![
+            vec![(0., 0.), (10., 0.)],
+            vec![(10., 0.), (10., 10.)],
+            vec![(10., 10.), (0., 0.), (10., 0.)]
+        ]);
+        assert_eq!(skipped.get("SPLINE"), Some(&1));
+    }
+
+    #[test]
+    fn written_lines_and_arcs_are_read_back() {
+        let points = [(0., 0.), (10., 0.), (0., 10.)].map(|(x, y)| TBPoint { x, y, r: 1., ..Default::default() });
+        let prims = [[0, 1, -1], [1, 2, 0]].map(|p| TPrimitive { p, ..Default::default() });
+        let model = Model::from_store(Box::new(OwnModel { points: points.to_vec(), prims: prims.to_vec(), ..OwnModel::default() }));
+        let (read, _) = read_dxf(&write_dxf(&model), 1.).unwrap();
+        assert_eq!(prim_positions(&read), prim_positions(&model));
+    }
+
+    #[test]
+    fn mirrored_arc_is_flipped() {
+        let text = "0\nSECTION\n2\nENTITIES\n0\nARC\n8\n0\n10\n2\n20\n0\n40\n1\n50\n0\n51\n90\n230\n-1\n0\nENDSEC\n0\nEOF\n";
+        let (model, _) = read_dxf(text, 1.).unwrap();
+        assert_eq!(prim_positions(&model), vec![vec![(-2., 1.), (-3., 0.), (-2., 0.)]]);
+    }
+}
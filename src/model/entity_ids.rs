@@ -0,0 +1,89 @@
+//! Persistent IDs of points, prims and regions.
+//!
+//! The core only knows dense indices, which shift when a record is deleted.
+//! The IDs move together with their records, so the number shown for a point stays the same
+//! across deletes, undo and save/load.
+
+pub type EntityId = u64;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Entity {
+    Point,
+    Prim,
+    Region
+}
+
+/// IDs in the order of the records. A new ID is never given twice, even after [EntityIds::clear].
+#[derive(Debug, Default, Clone)]
+pub struct EntityIds {
+    points: Vec<EntityId>,
+    prims: Vec<EntityId>,
+    regions: Vec<EntityId>,
+    /// Last given ID.
+    last: EntityId
+}
+
+impl EntityIds {
+    fn list(&self, entity: Entity) -> &Vec<EntityId> {
+        match entity {
+            Entity::Point => &self.points,
+            Entity::Prim => &self.prims,
+            Entity::Region => &self.regions
+        }
+    }
+    fn list_mut(&mut self, entity: Entity) -> &mut Vec<EntityId> {
+        match entity {
+            Entity::Point => &mut self.points,
+            Entity::Prim => &mut self.prims,
+            Entity::Region => &mut self.regions
+        }
+    }
+
+    pub fn get(&self, entity: Entity, index: usize) -> Option<EntityId> {
+        self.list(entity).get(index).copied()
+    }
+    /// Gives the record an ID it had before, e.g. on undo or when a file is loaded.
+    /// Another record that holds this ID already gets a new one, so IDs stay unique.
+    pub fn set(&mut self, entity: Entity, index: usize, id: EntityId) {
+        if index >= self.list(entity).len() {
+            return
+        }
+        self.last = self.last.max(id);
+        let holder = self.list(entity).iter().position(|&old| old == id);
+        if let Some(holder) = holder.filter(|&holder| holder != index) {
+            self.last += 1;
+            self.list_mut(entity)[holder] = self.last
+        }
+        self.list_mut(entity)[index] = id
+    }
+
+    /// A new record at `index` gets a new ID.
+    pub fn insert(&mut self, entity: Entity, index: usize) {
+        self.last += 1;
+        let id = self.last;
+        let list = self.list_mut(entity);
+        list.insert(index.min(list.len()), id)
+    }
+    pub fn remove(&mut self, entity: Entity, index: usize) {
+        let list = self.list_mut(entity);
+        if index < list.len() {
+            list.remove(index);
+        }
+    }
+
+    /// Follows a change of the length made by the core: new records at the end get new IDs,
+    /// IDs of records that are gone are dropped.
+    pub fn fit(&mut self, entity: Entity, len: usize) {
+        self.list_mut(entity).truncate(len);
+        while self.list(entity).len() < len {
+            let index = self.list(entity).len();
+            self.insert(entity, index)
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.points.clear();
+        self.prims.clear();
+        self.regions.clear();
+    }
+}
@@ -15,6 +15,10 @@ pub struct Framework<'a> {
     pub scale: f32,
     pub app_config: &'a AppConfig,
     pub mode: &'static str,
+    /// Prims highlighted in "Boundary" mode.
+    pub chosen_prims: &'a [usize],
+    /// Region shown in the inspector.
+    pub chosen_region: Option<usize>,
 }
 
 
@@ -72,7 +76,11 @@ impl canvas::Program<Message> for Framework<'_> {
             self.model.draw_model(frame, self.scale, self.app_config);
         });
 
-        vec![content, state.editing(&self.model, renderer, bounds, cursor, self.scale, &self.app_config.zoom)]
+        let mut chosen = canvas::Frame::new(renderer, bounds.size());
+        self.model.draw_chosen_prims(&mut chosen, self.chosen_prims, self.scale, self.app_config);
+        self.model.draw_chosen_region(&mut chosen, self.chosen_region, self.scale, self.app_config);
+
+        vec![content, chosen.into_geometry(), state.editing(self.model, renderer, bounds, cursor, self.scale, &self.app_config.zoom)]
     }
     
     fn mouse_interaction(&self, _state: &Self::State, bounds: Rectangle, cursor: Cursor) -> Interaction {
@@ -91,7 +99,7 @@ impl Framework<'_> {
             mouse::Event::ButtonPressed(mouse::Button::Left) => {
                 if state.as_str() != self.mode {
                     *state = match self.mode {
-                        "Point" | "Region" | "Find" => { Drawing::Point {} }
+                        "Point" | "Region" | "Find" | "Boundary" => { Drawing::Point {} }
                         "Line" => { Drawing::Line {} }
                         "Arc" => { Drawing::Arc }
                         _ => Drawing::None {}
@@ -110,13 +118,13 @@ impl Framework<'_> {
                     }
                     Drawing::LinePoint { mut point, num } => {
                         *state = Drawing::Line {};
-                        if num.is_some() && num.unwrap() < self.model.points_len() {
-                            point = self.model.points(num.unwrap())
+                        if let Some(num) = num.filter(|&num| num < self.model.points_len()) {
+                            point = self.model.points(num)
                         }
 
                         Some(Message::DefPrim(vec![point, real_cursor], (0, 1, -1)))
                     }
-                    Drawing::Arc {} => {
+                    Drawing::Arc => {
                         *state = Drawing::ArcPoint { point: real_cursor, num: None };
 
                         None
@@ -129,11 +137,11 @@ impl Framework<'_> {
                     Drawing::ArcTwoPoints { mut point_one, mut point_two, num_one, num_two } => {
                         *state = Drawing::Arc {};
 
-                        if num_one.is_some() && num_one.unwrap() < self.model.points_len() {
-                            point_one = self.model.points(num_one.unwrap())
+                        if let Some(num_one) = num_one.filter(|&num| num < self.model.points_len()) {
+                            point_one = self.model.points(num_one)
                         }
-                        if num_two.is_some() && num_two.unwrap() < self.model.points_len() {
-                            point_two = self.model.points(num_two.unwrap())
+                        if let Some(num_two) = num_two.filter(|&num| num < self.model.points_len()) {
+                            point_two = self.model.points(num_two)
                         }
                         
                         Some(Message::DefPrim(vec![point_one, point_two, real_cursor], (0, 1, 2)))
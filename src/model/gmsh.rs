@@ -0,0 +1,375 @@
+//! Gmsh `.msh` import and export of the mesh.
+//!
+//! ASCII files of versions 2.x, 4.0 and 4.1 are read: the nodes and the triangles (quadrangles are split in two,
+//! the corners of second order elements are taken). The physical tag of a triangle is its region: `IZP` is the tag minus one.
+//! The export writes version 4.1 with a physical surface for every region and a physical curve for its `Gran` boundary.
+//! Points and prims with their boundary conditions are not written, the export says so.
+
+use std::collections::HashMap;
+use std::fmt::Write;
+use super::borrow_types::{TElement, TNode};
+use super::own_model::OwnModel;
+use super::validate::elem_fits;
+use super::Model;
+
+/// Element types of Gmsh with the number of corners taken from them: triangles of order 1 and 2, quadrangles of order 1 and 2.
+const SURFACE_TYPES: [(u32, usize); 5] = [(2, 3), (9, 3), (3, 4), (10, 4), (16, 4)];
+const TRIANGLE_TYPE: u32 = 2;
+const LINE_TYPE: u32 = 1;
+
+/// Lines of a `$Section` with their numbers, read line by line or number by number.
+struct Section<'a> {
+    name: &'a str,
+    lines: Vec<(usize, &'a str)>,
+    next_line: usize,
+    /// Numbers left on the current line and its number.
+    rest: Vec<&'a str>,
+    line: usize
+}
+
+impl<'a> Section<'a> {
+    fn find(text: &'a str, name: &'a str) -> Option<Self> {
+        let mut lines = text.lines().enumerate().map(|(index, line)| (index + 1, line.trim()));
+        let (start, _) = lines.find(|(_, line)| line.strip_prefix('$') == Some(name))?;
+        let lines: Vec<_> = lines.take_while(|(_, line)| !line.starts_with("$End")).filter(|(_, line)| !line.is_empty()).collect();
+        Some(Self { name, lines, next_line: 0, rest: vec![], line: start })
+    }
+
+    fn error(&self, message: &str) -> String {
+        format!("line {}, ${}: {message}", self.line, self.name)
+    }
+
+    /// Words of the next line, the rest of the current one is dropped.
+    fn words(&mut self) -> Result<Vec<&'a str>, String> {
+        let (line, text) = *self.lines.get(self.next_line).ok_or_else(|| self.error("the section ends too early"))?;
+        self.next_line += 1;
+        self.line = line;
+        self.rest.clear();
+        Ok(text.split_whitespace().collect())
+    }
+
+    /// Next number, on this line or the following ones.
+    fn number<T: std::str::FromStr>(&mut self) -> Result<T, String> {
+        while self.rest.is_empty() {
+            let mut words = self.words()?;
+            words.reverse();
+            self.rest = words;
+        }
+        let word = self.rest.pop().unwrap_or_default();
+        word.parse().map_err(|_| self.error(&format!("{word} is not a number")))
+    }
+
+    fn parse<T: std::str::FromStr>(&self, word: Option<&&str>) -> Result<T, String> {
+        let word = word.ok_or_else(|| self.error("the line is too short"))?;
+        word.parse().map_err(|_| self.error(&format!("{word} is not a number")))
+    }
+}
+
+/// Mesh read from the file, node tags are still Gmsh tags.
+#[derive(Default)]
+struct MshMesh {
+    nodes: Vec<(usize, f64, f64)>,
+    /// Corners and the physical tag, 0 without one.
+    faces: Vec<(Vec<usize>, i32)>,
+    skipped: usize
+}
+
+impl MshMesh {
+    /// Keeps a surface element, counts the others.
+    fn element(&mut self, element_type: u32, nodes: &[usize], physical: i32) {
+        match SURFACE_TYPES.iter().find(|&&(surface_type, _)| surface_type == element_type) {
+            Some(&(_, corners)) if nodes.len() >= corners => self.faces.push((nodes[..corners].to_vec(), physical)),
+            _ => self.skipped += 1
+        }
+    }
+}
+
+/// Reads the mesh into an own model without points and prims.
+pub fn open_msh(path: &str) -> Result<Model, String> {
+    let text = std::fs::read(path.trim()).map_err(|error| format!("{}: {error}", path.trim()))?;
+    let text = String::from_utf8_lossy(&text);
+    let mesh = read_msh(&text).map_err(|error| format!("{}: {error}", path.trim()))?;
+    if mesh.skipped > 0 {
+        println!("{}: {} elements besides triangles and quadrangles are skipped", path.trim(), mesh.skipped)
+    }
+
+    let index_of: HashMap<usize, i32> = mesh.nodes.iter().enumerate().map(|(index, &(tag, _, _))| (tag, index as i32)).collect();
+    let mut own = OwnModel {
+        nodes: mesh.nodes.iter().map(|&(_, x, y)| TNode { x, y, ..Default::default() }).collect(),
+        ..OwnModel::default()
+    };
+    for (corners, physical) in mesh.faces {
+        let corners = corners.iter()
+            .map(|tag| index_of.get(tag).copied().ok_or_else(|| format!("{}: element node {tag} does not exist", path.trim())))
+            .collect::<Result<Vec<i32>, String>>()?;
+        let region = i16::try_from(physical - 1).unwrap_or(-1);
+        let triangles = match corners[..] {
+            [a, b, c, d] => vec![[a, b, c], [a, c, d]],
+            [a, b, c] => vec![[a, b, c]],
+            _ => vec![]
+        };
+        own.elems.extend(triangles.into_iter().map(|m| TElement { m, IZP: region, ..Default::default() }));
+    }
+    own.link_nodes();
+    Ok(Model::from_store(Box::new(own)))
+}
+
+fn read_msh(text: &str) -> Result<MshMesh, String> {
+    let mut format = Section::find(text, "MeshFormat").ok_or("No $MeshFormat, this is not a Gmsh file")?;
+    let words = format.words()?;
+    let version: f64 = format.parse(words.first())?;
+    let file_type: u32 = format.parse(words.get(1))?;
+    if file_type != 0 {
+        return Err(String::from("Binary .msh files are not supported, save the mesh as ASCII"))
+    }
+    if (2.0..3.0).contains(&version) {
+        read_msh_2(text)
+    } else if (4.0..5.0).contains(&version) {
+        read_msh_4(text, version < 4.1)
+    } else {
+        Err(format!("Version {version} is not supported, save the mesh as version 2.2 or 4.1"))
+    }
+}
+
+fn read_msh_2(text: &str) -> Result<MshMesh, String> {
+    let mut mesh = MshMesh::default();
+    let mut nodes = Section::find(text, "Nodes").ok_or("No $Nodes")?;
+    for _ in 0..nodes.number::<usize>()? {
+        let words = nodes.words()?;
+        mesh.nodes.push((nodes.parse(words.first())?, nodes.parse(words.get(1))?, nodes.parse(words.get(2))?));
+    }
+
+    let mut elements = Section::find(text, "Elements").ok_or("No $Elements")?;
+    for _ in 0..elements.number::<usize>()? {
+        // tag, type, number of tags, the tags (physical first), the nodes
+        let words = elements.words()?;
+        let element_type: u32 = elements.parse(words.get(1))?;
+        let tags: usize = elements.parse(words.get(2))?;
+        let physical = if tags > 0 { elements.parse(words.get(3))? } else { 0 };
+        let element_nodes = words.iter().skip(3 + tags).map(|word| elements.parse(Some(word))).collect::<Result<Vec<usize>, String>>()?;
+        mesh.element(element_type, &element_nodes, physical);
+    }
+    Ok(mesh)
+}
+
+/// Version 4.0 differs from 4.1 in the layout: points of `$Entities` have a bounding box,
+/// the headers of `$Nodes` and `$Elements` have no tag range, a block starts with the entity tag
+/// before its dimension, and every node line has its tag before the coordinates.
+fn read_msh_4(text: &str, v4_0: bool) -> Result<MshMesh, String> {
+    let mut mesh = MshMesh::default();
+
+    // Physical tags belong to the entities, the elements only name their entity.
+    let mut physical_of: HashMap<(u32, i32), i32> = HashMap::new();
+    if let Some(mut entities) = Section::find(text, "Entities") {
+        let words = entities.words()?;
+        let counts = (0..4).map(|dim| entities.parse(words.get(dim))).collect::<Result<Vec<usize>, String>>()?;
+        for (dim, &count) in counts.iter().enumerate() {
+            for _ in 0..count {
+                let words = entities.words()?;
+                let tag = entities.parse(words.first())?;
+                // a point has its position since 4.1, the others have a bounding box
+                let physicals_at = if dim == 0 && !v4_0 { 4 } else { 7 };
+                let physicals: usize = entities.parse(words.get(physicals_at))?;
+                if physicals > 0 {
+                    physical_of.insert((dim as u32, tag), entities.parse(words.get(physicals_at + 1))?);
+                }
+            }
+        }
+    }
+
+    let mut nodes = Section::find(text, "Nodes").ok_or("No $Nodes")?;
+    let blocks: usize = nodes.number()?;
+    for _ in 0..if v4_0 { 1 } else { 3 } {
+        nodes.number::<usize>()?;
+    }
+    for _ in 0..blocks {
+        let dim: usize = if v4_0 {
+            nodes.number::<i32>()?;
+            nodes.number()?
+        } else {
+            let dim = nodes.number()?;
+            nodes.number::<i32>()?;
+            dim
+        };
+        let parametric: u32 = nodes.number()?;
+        let count: usize = nodes.number()?;
+        let tags = if v4_0 { vec![] } else { (0..count).map(|_| nodes.number()).collect::<Result<Vec<usize>, String>>()? };
+        let mut tags = tags.into_iter();
+        for _ in 0..count {
+            let tag = if v4_0 { nodes.number()? } else { tags.next().unwrap_or_default() };
+            let (x, y, _z): (f64, f64, f64) = (nodes.number()?, nodes.number()?, nodes.number()?);
+            for _ in 0..if parametric == 1 { dim } else { 0 } {
+                nodes.number::<f64>()?;
+            }
+            mesh.nodes.push((tag, x, y));
+        }
+    }
+
+    let mut elements = Section::find(text, "Elements").ok_or("No $Elements")?;
+    let words = elements.words()?;
+    let blocks: usize = elements.parse(words.first())?;
+    for _ in 0..blocks {
+        let words = elements.words()?;
+        let (dim_at, entity_at) = if v4_0 { (1, 0) } else { (0, 1) };
+        let dim: u32 = elements.parse(words.get(dim_at))?;
+        let entity: i32 = elements.parse(words.get(entity_at))?;
+        let element_type: u32 = elements.parse(words.get(2))?;
+        let count: usize = elements.parse(words.get(3))?;
+        let physical = physical_of.get(&(dim, entity)).copied().unwrap_or_default();
+        for _ in 0..count {
+            let words = elements.words()?;
+            let element_nodes = words.iter().skip(1).map(|word| elements.parse(Some(word))).collect::<Result<Vec<usize>, String>>()?;
+            mesh.element(element_type, &element_nodes, physical);
+        }
+    }
+    Ok(mesh)
+}
+
+/// Bounding box of the nodes as Gmsh writes it for an entity.
+fn bounding_box(model: &Model, nodes: impl Iterator<Item = usize>) -> String {
+    let (mut min, mut max) = ((f64::MAX, f64::MAX), (f64::MIN, f64::MIN));
+    for node in nodes.filter_map(|node| model.t_node_ref(node)) {
+        (min.0, min.1) = (min.0.min(node.x), min.1.min(node.y));
+        (max.0, max.1) = (max.0.max(node.x), max.1.max(node.y));
+    }
+    if min.0 > max.0 {
+        return String::from("0 0 0 0 0 0")
+    }
+    format!("{} {} 0 {} {} 0", min.0, min.1, max.0, max.1)
+}
+
+/// Writes version 4.1: the nodes, the triangles on a surface per region and the `Gran` of every region as a curve.
+/// Region `i` (the `IZP` of the triangles) is the physical surface and curve `i + 1`,
+/// so an imported mesh keeps its tags without regions. Triangles without a region are on a surface without a physical tag.
+pub fn save_msh(path: &str, model: &Model) -> Result<(), String> {
+    std::fs::write(path.trim(), write_msh(model)).map_err(|error| format!("{}: {error}", path.trim()))?;
+    if model.prims_len() > 0 {
+        println!("{}: only the mesh is written, the points and prims with their boundary conditions (TypPrim, Vp) are not", path.trim())
+    }
+    Ok(())
+}
+
+fn write_msh(model: &Model) -> String {
+    let nodes_len = model.nodes_len();
+    let elems: Vec<(usize, Option<usize>)> = (0..model.elems_len())
+        .filter(|&index| elem_fits(model.elems(index), nodes_len))
+        .map(|index| (index, model.t_element_ref(index).and_then(|elem| usize::try_from(elem.IZP).ok())))
+        .collect();
+    let regions_len = elems.iter().filter_map(|&(_, region)| region.map(|region| region + 1)).fold(model.regions_len(), usize::max);
+
+    // triangles of every region and then the ones without a region
+    let mut surfaces: Vec<Vec<usize>> = vec![vec![]; regions_len + 1];
+    for (index, region) in elems {
+        surfaces[region.unwrap_or(regions_len)].push(index)
+    }
+    let boundaries: Vec<(usize, Vec<usize>)> = (0..model.regions_len())
+        .map(|region| (region, model.region_gran(region).iter().filter_map(|gran| usize::try_from(gran.Node).ok()).filter(|&node| node < nodes_len).collect::<Vec<_>>()))
+        .filter(|(_, nodes)| nodes.len() >= 2)
+        .collect();
+    let name = |region: usize| {
+        let name = model.t_region_ref(region).map(|region| region.mat_char_name()).unwrap_or_default();
+        if name.is_empty() { format!("Region {region}") } else { name.replace('"', "'") }
+    };
+
+    let mut out = String::new();
+    let _ = writeln!(out, "$MeshFormat\n4.1 0 8\n$EndMeshFormat");
+
+    let _ = writeln!(out, "$PhysicalNames\n{}", regions_len + boundaries.len());
+    for region in 0..regions_len {
+        let _ = writeln!(out, "2 {} \"{}\"", region + 1, name(region));
+    }
+    for (region, _) in &boundaries {
+        let _ = writeln!(out, "1 {} \"Boundary of {}\"", region + 1, name(*region));
+    }
+    let _ = writeln!(out, "$EndPhysicalNames");
+
+    let _ = writeln!(out, "$Entities\n0 {} {} 0", boundaries.len(), regions_len + 1);
+    for (region, nodes) in &boundaries {
+        let _ = writeln!(out, "{} {} 1 {} 0", region + 1, bounding_box(model, nodes.iter().copied()), region + 1);
+    }
+    for (surface, elems) in surfaces.iter().enumerate() {
+        let corners = elems.iter().flat_map(|&index| model.elems(index).map(|node| node as usize));
+        let physical = if surface < regions_len { format!("1 {}", surface + 1) } else { String::from("0") };
+        let _ = writeln!(out, "{} {} {physical} 0", surface + 1, bounding_box(model, corners));
+    }
+    let _ = writeln!(out, "$EndEntities");
+
+    // All nodes are on the surface without a region, Gmsh only needs them to belong to some entity.
+    let _ = writeln!(out, "$Nodes\n1 {nodes_len} {} {nodes_len}", nodes_len.min(1));
+    let _ = writeln!(out, "2 {} 0 {nodes_len}", regions_len + 1);
+    for tag in 1..=nodes_len {
+        let _ = writeln!(out, "{tag}");
+    }
+    for index in 0..nodes_len {
+        let node = model.t_node_ref(index).map_or((0., 0.), |node| (node.x, node.y));
+        let _ = writeln!(out, "{} {} 0", node.0, node.1);
+    }
+    let _ = writeln!(out, "$EndNodes");
+
+    let surface_blocks: Vec<(usize, &Vec<usize>)> = surfaces.iter().enumerate().filter(|(_, elems)| !elems.is_empty()).collect();
+    let lines_len: usize = boundaries.iter().map(|(_, nodes)| nodes.len()).sum();
+    let elements_len = surface_blocks.iter().map(|(_, elems)| elems.len()).sum::<usize>() + lines_len;
+    let _ = writeln!(out, "$Elements\n{} {elements_len} {} {elements_len}", surface_blocks.len() + boundaries.len(), elements_len.min(1));
+    let mut tag = 0;
+    for (surface, elems) in surface_blocks {
+        let _ = writeln!(out, "2 {} {TRIANGLE_TYPE} {}", surface + 1, elems.len());
+        for &index in elems {
+            tag += 1;
+            let [a, b, c] = model.elems(index).map(|node| node + 1);
+            let _ = writeln!(out, "{tag} {a} {b} {c}");
+        }
+    }
+    for (region, nodes) in &boundaries {
+        let _ = writeln!(out, "1 {} {LINE_TYPE} {}", region + 1, nodes.len());
+        for (i, &node) in nodes.iter().enumerate() {
+            tag += 1;
+            let _ = writeln!(out, "{tag} {} {}", node + 1, nodes[(i + 1) % nodes.len()] + 1);
+        }
+    }
+    let _ = writeln!(out, "$EndElements");
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn version_2_2_is_read() {
+        let text = "$MeshFormat\n2.2 0 8\n$EndMeshFormat\n\
+            $Nodes\n4\n1 0 0 0\n2 1 0 0\n3 1 1 0\n4 0 1 0\n$EndNodes\n\
+            $Elements\n3\n1 1 2 5 1 1 2\n2 2 2 3 1 1 2 3\n3 3 0 1 3 4 2\n$EndElements\n";
+        let mesh = read_msh(text).unwrap();
+        assert_eq!(mesh.nodes, vec![(1, 0., 0.), (2, 1., 0.), (3, 1., 1.), (4, 0., 1.)]);
+        assert_eq!(mesh.faces, vec![(vec![1, 2, 3], 3), (vec![1, 3, 4, 2], 0)]);
+        assert_eq!(mesh.skipped, 1);
+    }
+
+    #[test]
+    fn other_versions_and_binary_files_are_refused() {
+        assert!(read_msh("$MeshFormat\n3.0 0 8\n$EndMeshFormat\n").is_err_and(|error| error.contains("not supported")));
+        assert!(read_msh("$MeshFormat\n4.1 1 8\n$EndMeshFormat\n").is_err_and(|error| error.contains("Binary")));
+        assert!(read_msh("$Nodes\n0\n$EndNodes\n").is_err());
+    }
+
+    #[test]
+    fn written_mesh_is_read_back_with_its_regions() {
+        let nodes = [(0., 0.), (1., 0.), (1., 1.), (0., 1.)].map(|(x, y)| TNode { x, y, ..Default::default() });
+        let elems = [([0, 1, 2], 0), ([0, 2, 3], -1)].map(|(m, region)| TElement { m, IZP: region, ..Default::default() });
+        let model = Model::from_store(Box::new(OwnModel { nodes: nodes.into(), elems: elems.into(), ..OwnModel::default() }));
+        let mesh = read_msh(&write_msh(&model)).unwrap();
+        assert_eq!(mesh.nodes, vec![(1, 0., 0.), (2, 1., 0.), (3, 1., 1.), (4, 0., 1.)]);
+        assert_eq!(mesh.faces, vec![(vec![1, 2, 3], 1), (vec![1, 3, 4], 0)]);
+    }
+
+    #[test]
+    fn version_4_0_layout_is_read() {
+        let text = "$MeshFormat\n4 0 8\n$EndMeshFormat\n\
+            $Entities\n4 0 1 0\n1 0 0 0 0 0 0 0\n2 1 0 0 1 0 0 0\n3 1 1 0 1 1 0 0\n4 0 1 0 0 1 0 0\n1 0 0 0 1 1 0 1 2 0\n$EndEntities\n\
+            $Nodes\n1 4\n1 2 0 4\n1 0 0 0\n2 1 0 0\n3 1 1 0\n4 0 1 0\n$EndNodes\n\
+            $Elements\n1 2\n1 2 2 2\n1 1 2 3\n2 1 3 4\n$EndElements\n";
+        let mesh = read_msh(text).unwrap();
+        assert_eq!(mesh.nodes, vec![(1, 0., 0.), (2, 1., 0.), (3, 1., 1.), (4, 0., 1.)]);
+        assert_eq!(mesh.faces, vec![(vec![1, 2, 3], 2), (vec![1, 3, 4], 2)]);
+    }
+}
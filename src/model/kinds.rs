@@ -0,0 +1,70 @@
+//! Byte codes of the core records as enums.
+
+/// Enum over the byte codes of a core field. Only the codes FLib documents get a variant,
+/// other bytes become `Unknown(n)`, so they are shown and written back unchanged.
+macro_rules! byte_enum {
+    ($(#[$meta:meta])* $name:ident { $($(#[$variant_meta:meta])* $variant:ident = $byte:literal => $text:literal),* $(,)? }) => {
+        $(#[$meta])*
+        #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+        pub enum $name {
+            $($(#[$variant_meta])* $variant,)*
+            Unknown(u8)
+        }
+
+        impl $name {
+            /// Every known variant, for pick lists.
+            pub const ALL: &'static [Self] = &[$(Self::$variant),*];
+
+            // A field without documented codes matches only `Unknown`.
+            #[allow(clippy::match_single_binding)]
+            pub fn from_byte(byte: u8) -> Self {
+                match byte {
+                    $($byte => Self::$variant,)*
+                    other => Self::Unknown(other)
+                }
+            }
+
+            #[allow(clippy::match_single_binding)]
+            pub fn byte(self) -> u8 {
+                match self {
+                    $(Self::$variant => $byte,)*
+                    Self::Unknown(other) => other
+                }
+            }
+        }
+
+        impl From<$name> for u8 {
+            fn from(value: $name) -> u8 {
+                value.byte()
+            }
+        }
+
+        impl std::fmt::Display for $name {
+            #[allow(clippy::match_single_binding)]
+            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                match self {
+                    $(Self::$variant => f.write_str($text),)*
+                    Self::Unknown(other) => write!(f, "unknown({other})")
+                }
+            }
+        }
+    };
+}
+pub(crate) use byte_enum;
+
+// FLib documents no codes of the fields below, so every byte stays `Unknown(n)` and is typed as a number.
+
+byte_enum! {
+    /// `TBPoint::TypPoint`.
+    PointType {}
+}
+
+byte_enum! {
+    /// `TNode::TypNode`, set by the core while meshing.
+    NodeType {}
+}
+
+byte_enum! {
+    /// `TRegion::PrMag`, how the material of the region is described.
+    MagnetType {}
+}
@@ -1,72 +1,78 @@
-use std::rc::Rc;
+use std::sync::Arc;
 use crate::model::Model;
-use iced::Point;
-use csv::{WriterBuilder, ReaderBuilder};
-use libloading::Library;
-use crate::foreign_functions::{f_open_dat, f_save_dat};
+use crate::model::entity_ids::{Entity, EntityId};
+use csv::{WriterBuilder, ReaderBuilder, StringRecord, Position, ErrorKind};
+use super::borrow_types::{TBPoint, TPrimitive, TNode, TElement};
+use super::boundary::Boundary;
+use super::{gmsh, vtk};
+use super::own_model::OwnModel;
+use crate::math_core::MathCore;
 
 #[derive(serde::Serialize, serde::Deserialize, Debug)]
 struct Csv {
-    p1: Option<f32>,
-    p2: Option<f32>,
-    p3: Option<f32>,
+    p1: Option<f64>,
+    p2: Option<f64>,
+    p3: Option<f64>,
+    /// Persistent ID of the point, see [crate::model::entity_ids].
+    p_id: Option<EntityId>,
     l1: Option<i32>,
     l2: Option<i32>,
     l3: Option<i32>,
-    n_p1: Option<f32>,
-    n_p2: Option<f32>,
+    /// `TypPrim` of the line, see [crate::model::boundary::Boundary].
+    l_typ: Option<u8>,
+    /// `Vp` of the line.
+    l_vp: Option<f64>,
+    /// Persistent ID of the line.
+    l_id: Option<EntityId>,
+    n_p1: Option<f64>,
+    n_p2: Option<f64>,
     n_l1: Option<i32>,
     n_l2: Option<i32>,
-    n_l3: Option<i32>
+    n_l3: Option<i32>,
+    /// Region of the element, `IZP`.
+    n_izp: Option<i16>
 }
 
 impl Csv {
     #[inline]
-    fn new(points: Option<(Point, f32)>, lines: Option<&[i32; 3]>, node_points: Option<Point>, node_lines: Option<&[i32; 3]>) -> Self {
-        let (p1, p2, p3) = match points {
-            None => (None, None, None),
-            _ => {
-                let p = points.as_ref().unwrap();
-                (Some(p.0.x), Some(p.0.y), Some(p.1))
-            }
-        };
-        let (l1, l2, l3) = match lines {
-            None => (None, None, None),
-            _ => {
-                let l = lines.as_ref().unwrap();
-                (Some(l[0]), Some(l[1]), Some(l[2]))
-            }
-        };
-        let (n_p1, n_p2) = match node_points {
-            None => (None, None),
-            _ => {
-                let p = node_points.as_ref().unwrap();
-                (Some(p.x), Some(p.y))
-            }
-        };
-        let (n_l1, n_l2, n_l3) = match node_lines {
-            None => (None, None, None),
-            _ => {
-                let nl = node_lines.as_ref().unwrap();
-                (Some(nl[0]), Some(nl[1]), Some(nl[2]))
-            }
-        };
-        
+    fn new(point: Option<&TBPoint>, prim: Option<&TPrimitive>, node: Option<&TNode>, elem: Option<&TElement>) -> Self {
         Self {
-            p1, p2, p3,
-            l1, l2, l3,
-            n_p1, n_p2,
-            n_l1, n_l2, n_l3
+            p1: point.map(|point| point.x),
+            p2: point.map(|point| point.y),
+            p3: point.map(|point| point.r),
+            p_id: None,
+            l1: prim.map(|prim| prim.p[0]),
+            l2: prim.map(|prim| prim.p[1]),
+            l3: prim.map(|prim| prim.p[2]),
+            l_typ: prim.map(|prim| prim.TypPrim),
+            l_vp: prim.map(|prim| prim.Vp),
+            l_id: None,
+            n_p1: node.map(|node| node.x),
+            n_p2: node.map(|node| node.y),
+            n_l1: elem.map(|elem| elem.m[0]),
+            n_l2: elem.map(|elem| elem.m[1]),
+            n_l3: elem.map(|elem| elem.m[2]),
+            n_izp: elem.map(|elem| elem.IZP)
         }
     }
 }
 
-pub fn open_model(lib: &Option<Rc<Library>>, path: String, model: &mut Model) -> bool {
+pub fn open_model(core: &Option<Arc<dyn MathCore>>, path: String, model: &mut Model) -> bool {
     let path = path.trim().to_string();
     if path.len() >= 3 {
         match path.get((path.len() - 3)..=(path.len() - 1)) {
             Some("csv") => open_csv_model(path, model),
-            Some("mke") | Some("fmd") => open_bin_model(lib, path),
+            Some("mke") | Some("fmd") => open_bin_model(core, path, model),
+            Some("msh") => match gmsh::open_msh(&path) {
+                Ok(opened) => {
+                    *model = opened;
+                    true
+                }
+                Err(error) => {
+                    println!("{error}");
+                    false
+                }
+            },
             _ => false
         }
     }
@@ -75,56 +81,157 @@ pub fn open_model(lib: &Option<Rc<Library>>, path: String, model: &mut Model) ->
     }
 }
 
-fn open_csv_model (path: String, model: &mut Model) -> bool {
-    if let Ok(mut reader) = ReaderBuilder::new().delimiter(b'\t').from_path(path) {
-        let records = reader.deserialize::<Csv>();
+/// Reads the whole file into an own model, so a saved mesh is shown without the core.
+/// Broken rows are reported with their lines and then nothing is opened.
+fn open_csv_model(path: String, model: &mut Model) -> bool {
+    let read = std::fs::File::open(&path)
+        .map_err(|error| vec![error.to_string()])
+        .and_then(read_csv);
+    match read {
+        Ok(opened) => {
+            *model = opened;
+            true
+        }
+        Err(errors) => {
+            for error in &errors {
+                println!("{path}: {error}")
+            }
+            false
+        }
+    }
+}
 
-        for i in records {
-            if let Ok(rec) = i.as_ref() {
-                 if let (Some(p1), Some(p2), Some(p3)) = (rec.p1, rec.p2, rec.p3) {
-                    model.points_push(Point::new(p1, p2), p3)
-                }
+/// The own model of the CSV text, or the errors of every broken row.
+fn read_csv(source: impl std::io::Read) -> Result<Model, Vec<String>> {
+    let mut reader = ReaderBuilder::new().delimiter(b'\t').from_reader(source);
+    let headers = match reader.headers() {
+        Ok(headers) => headers.clone(),
+        Err(error) => return Err(vec![csv_error(&error, &StringRecord::new())])
+    };
 
-                if let (Some(l1), Some(l2), Some(l3)) = (rec.l1, rec.l2, rec.l3) {
-                    model.prims_push([l1, l2, l3])
-                }
+    let mut own = OwnModel::default();
+    let mut ids = vec![];
+    let mut errors = vec![];
+    let mut row = StringRecord::new();
+    loop {
+        match reader.read_record(&mut row) {
+            Ok(true) => {}
+            Ok(false) => break,
+            Err(error) if error.is_io_error() => {
+                errors.push(csv_error(&error, &headers));
+                break
+            }
+            Err(error) => {
+                errors.push(csv_error(&error, &headers));
+                continue
+            }
+        }
+        let rec: Csv = match row.deserialize(Some(&headers)) {
+            Ok(rec) => rec,
+            Err(error) => {
+                errors.push(csv_error(&error, &headers));
+                continue
+            }
+        };
+        let line = row.position().map_or(0, Position::line);
+        let mut report = |message: &str| errors.push(format!("line {line}: {message}"));
 
-                if let (Some(np1), Some(np2)) = (rec.n_p1, rec.n_p2) {
-                    //model.node_dots.push(Point::new(np1, np2));
+        match (rec.p1, rec.p2, rec.p3) {
+            (Some(x), Some(y), Some(r)) => {
+                own.points.push(TBPoint { x, y, r, ..Default::default() });
+                if let Some(id) = rec.p_id {
+                    ids.push((Entity::Point, own.points.len() - 1, id))
                 }
+            }
+            (None, None, None) => {}
+            _ => report("a point needs p1, p2 and p3")
+        }
 
-                if let(Some(n_l1), Some(n_l2), Some(n_l3)) = (rec.n_l1, rec.n_l2, rec.n_l3) {
-                    //node_lines.push([n_l1, n_l2, n_l3])
+        match (rec.l1, rec.l2, rec.l3) {
+            (Some(l1), Some(l2), Some(l3)) => {
+                own.prims.push(TPrimitive {
+                    p: [l1, l2, l3],
+                    TypPrim: rec.l_typ.unwrap_or(Boundary::default().byte()),
+                    Vp: rec.l_vp.unwrap_or(Boundary::DEFAULT_VP),
+                    ..Default::default()
+                });
+                if let Some(id) = rec.l_id {
+                    ids.push((Entity::Prim, own.prims.len() - 1, id))
                 }
-                
             }
+            (None, None, None) => {}
+            _ => report("a line needs l1, l2 and l3")
+        }
 
+        match (rec.n_p1, rec.n_p2) {
+            (Some(x), Some(y)) => own.nodes.push(TNode { x, y, ..Default::default() }),
+            (None, None) => {}
+            _ => report("a node needs n_p1 and n_p2")
+        }
+
+        match (rec.n_l1, rec.n_l2, rec.n_l3) {
+            (Some(n_l1), Some(n_l2), Some(n_l3)) => {
+                own.elems.push(TElement { m: [n_l1, n_l2, n_l3], IZP: rec.n_izp.unwrap_or(-1), ..Default::default() })
+            }
+            (None, None, None) => {}
+            _ => report("an element needs n_l1, n_l2 and n_l3")
         }
-        
-        return true
     }
 
-    false
+    if !errors.is_empty() {
+        return Err(errors)
+    }
+
+    own.link_nodes();
+    let mut model = Model::from_store(Box::new(own));
+    for (entity, index, id) in ids {
+        model.set_id(entity, index, id)
+    }
+    Ok(model)
 }
 
-fn open_bin_model(lib: &Option<Rc<Library>>, path: String) -> bool {
-    if let Some(real_lib) = lib {
-        if !f_open_dat(real_lib.clone(), &path) {
-            return false;
+/// Message of a read or parse error with its line and column name.
+fn csv_error(error: &csv::Error, headers: &StringRecord) -> String {
+    let Some(line) = error.position().map(Position::line) else {
+        return error.to_string()
+    };
+    match error.kind() {
+        ErrorKind::UnequalLengths { expected_len, len, .. } => format!("line {line}: {len} columns instead of {expected_len}"),
+        ErrorKind::Deserialize { err, .. } => {
+            let column = err.field().and_then(|field| headers.get(field as usize)).unwrap_or("?");
+            format!("line {line}, column {column}: {}", err.kind())
         }
-        true
+        _ => format!("line {line}: {error}")
     }
-    else {
-        false
+}
+
+/// The core replaces its model, so the borrowed one fetches the arrays again.
+fn open_bin_model(core: &Option<Arc<dyn MathCore>>, path: String, model: &mut Model) -> bool {
+    match core {
+        Some(core) if model.is_borrowed() => {
+            let out = core.open_dat(&path);
+            model.sync_everything();
+            out
+        }
+        _ => {
+            println!("{path}: send the model to the math core first, only the core reads this format");
+            false
+        }
     }
 }
 
-pub fn export_model(lib: &Option<Rc<Library>>, path: String, model: &Model) -> bool {
+pub fn export_model(core: &Option<Arc<dyn MathCore>>, path: String, model: &Model) -> bool {
     let path = path.trim().to_string();
     if path.len() >= 4 {
         match path.get((path.len() - 3)..=(path.len() - 1)) {
             Some("csv") => export_csv_model(path, model),
-            Some("mke") => export_bin_model(lib, path),
+            Some("mke") | Some("fmd") => export_bin_model(core, path, model),
+            Some("msh") => gmsh::save_msh(&path, model)
+                .map_err(|error| println!("{error}"))
+                .is_ok(),
+            Some("vtu") => vtk::save_vtu(&path, model)
+                .map_err(|error| println!("{error}"))
+                .is_ok(),
             _ => false
         }
     }
@@ -134,30 +241,102 @@ pub fn export_model(lib: &Option<Rc<Library>>, path: String, model: &Model) -> b
 }
 
 fn export_csv_model(path: String, model: &Model) -> bool {
-    if let Ok(mut writer) = WriterBuilder::new().delimiter(b'\t').from_path(path) {
-        let max_len = usize::max(model.points_len(), model.points_len());
+    let written = std::fs::File::create(&path)
+        .map_err(|error| error.to_string())
+        .and_then(|file| write_csv(file, model));
+    if let Err(error) = &written {
+        println!("{path}: {error}")
+    }
+    written.is_ok()
+}
 
-        for i in 0..max_len {
-            let rec = Csv::new(
-                if i < model.points_len() {Some((model.points(i), model.points_r(i)))} else {None},
-                if i < model.prims_len() {Some(model.prims(i))} else {None},
-                if i < model.nodes_len() {Some(model.nodes(i))} else {None},
-                if i < model.elems_len() {Some(model.elems(i))} else {None},
-            );
+/// A row per index up to the longest of the points, prims, nodes and elements.
+fn write_csv(target: impl std::io::Write, model: &Model) -> Result<(), String> {
+    let mut writer = WriterBuilder::new().delimiter(b'\t').from_writer(target);
+    let max_len = [model.points_len(), model.prims_len(), model.nodes_len(), model.elems_len()]
+        .into_iter()
+        .max()
+        .unwrap_or_default();
+
+    for i in 0..max_len {
+        let mut rec = Csv::new(model.tb_point_ref(i), model.t_primitive_ref(i), model.t_node_ref(i), model.t_element_ref(i));
+        rec.p_id = model.id(Entity::Point, i);
+        rec.l_id = model.id(Entity::Prim, i);
+        writer.serialize(rec).map_err(|error| error.to_string())?;
+    }
+    writer.flush().map_err(|error| error.to_string())
+}
 
-            writer.serialize(rec).expect("No write");
+/// Saves the model of the core, so only a sent model is written.
+fn export_bin_model(core: &Option<Arc<dyn MathCore>>, path: String, model: &Model) -> bool {
+    match core {
+        Some(core) if model.is_borrowed() => core.save_dat(&path),
+        _ => {
+            println!("{path}: send the model to the math core first, only the core writes this format");
+            false
         }
-        writer.flush().expect("No flush");
-        return true
     }
-    false
 }
 
-fn export_bin_model(lib: &Option<Rc<Library>>, path: String) -> bool {
-    if let Some(real_lib) = lib {
-        f_save_dat(real_lib.clone(), path)
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn csv_round_trip_keeps_f64_positions_and_elements() {
+        let mut own = OwnModel {
+            points: vec![TBPoint { x: 0.1 + 0.2, y: 1e-9, r: 20., ..Default::default() }, TBPoint { x: 1. / 3., y: 7., r: 2.5, ..Default::default() }],
+            prims: vec![TPrimitive { p: [0, 1, -1], TypPrim: 2, Vp: 0.25, ..Default::default() }],
+            nodes: [(0., 0.), (1. / 3., 0.), (0.1 + 0.2, 1. / 7.)].into_iter().map(|(x, y)| TNode { x, y, ..Default::default() }).collect(),
+            elems: vec![TElement { m: [0, 1, 2], IZP: 2, ..Default::default() }],
+            ..OwnModel::default()
+        };
+        own.link_nodes();
+        let mut model = Model::from_store(Box::new(own));
+        model.set_id(Entity::Point, 1, 40);
+
+        let mut text = vec![];
+        write_csv(&mut text, &model).unwrap();
+        let opened = read_csv(text.as_slice()).unwrap();
+
+        for i in 0..2 {
+            let (saved, read) = (model.tb_point_ref(i).unwrap(), opened.tb_point_ref(i).unwrap());
+            assert_eq!((saved.x, saved.y, saved.r), (read.x, read.y, read.r));
+        }
+        assert_eq!(opened.prims(0), &[0, 1, -1]);
+        assert_eq!(opened.prim_condition(0), (2, 0.25));
+        for i in 0..3 {
+            let (saved, read) = (model.t_node_ref(i).unwrap(), opened.t_node_ref(i).unwrap());
+            assert_eq!((saved.x, saved.y), (read.x, read.y));
+        }
+        assert_eq!(opened.t_element_ref(0).map(|elem| (elem.m, elem.IZP)), Some(([0, 1, 2], 2)));
+        assert_eq!(opened.id(Entity::Point, 1), Some(40));
+        assert!(!opened.node_links(0).is_empty());
     }
-    else {
-        false
+
+    #[test]
+    fn csv_rows_without_new_columns_get_defaults() {
+        let text = "p1\tp2\tp3\tl1\tl2\tl3\tn_p1\tn_p2\tn_l1\tn_l2\tn_l3\n0\t0\t20\t0\t1\t-1\t0\t0\t0\t1\t2\n1\t0\t20\t\t\t\t1\t0\t\t\t\n\t\t\t\t\t\t0\t1\t\t\t\n";
+        let opened = read_csv(text.as_bytes()).unwrap();
+        assert_eq!(opened.prim_condition(0), (Boundary::default().byte(), Boundary::DEFAULT_VP));
+        assert_eq!(opened.t_element_ref(0).map(|elem| elem.IZP), Some(-1));
+    }
+
+    #[test]
+    fn csv_ids_on_some_rows_stay_unique() {
+        let opened = read_csv("p1\tp2\tp3\tp_id\n0\t0\t20\t\n1\t0\t20\t1\n2\t0\t20\t\n".as_bytes()).unwrap();
+        let mut ids: Vec<_> = (0..3).filter_map(|i| opened.id(Entity::Point, i)).collect();
+        assert_eq!(ids[1], 1);
+        ids.sort();
+        ids.dedup();
+        assert_eq!(ids.len(), 3);
+    }
+
+    #[test]
+    fn broken_csv_rows_are_reported_with_their_lines() {
+        let errors = read_csv("p1\tp2\tp3\n0\t0\n1\tx\t20\n".as_bytes()).err().unwrap();
+        assert_eq!(errors.len(), 2);
+        assert!(errors[0].starts_with("line 2"));
+        assert!(errors[1].starts_with("line 3, column p2"));
     }
 }
@@ -2,9 +2,22 @@ pub mod model_impl;
 pub mod framework;
 pub mod load_model;
 mod drawing;
+#[allow(clippy::module_inception)]
 pub mod model;
 pub(crate) mod borrow_model;
+pub(crate) mod store;
 mod own_model;
+pub mod entity_ids;
+pub mod validate;
+pub mod project;
+pub mod dxf;
+pub mod painter;
+pub mod svg;
+pub mod png;
+pub mod gmsh;
+pub mod vtk;
 pub(crate) mod borrow_types;
+pub mod boundary;
+pub mod kinds;
 
 pub use model::Model;
\ No newline at end of file
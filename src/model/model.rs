@@ -1,231 +1,409 @@
 use iced::Point;
-use libloading::Library;
+use std::sync::Arc;
+use crate::math_core::MathCore;
 use crate::app::undo_manager::UndoManager;
 use super::borrow_model::*;
 use super::own_model::*;
 use super::borrow_types::*;
+use super::entity_ids::{Entity, EntityId};
+use super::store::ModelStore;
+use super::validate::{elem_fits, prim_fits};
 
 /// Tools to draw [Framework].
+///
+/// The records are kept by a [ModelStore]. What a store can do is asked with
+/// [Model::has_mesh], [Model::has_field], [Model::editable_physics] and [Model::is_borrowed].
 #[derive(Debug)]
-pub enum Model {
-    Own {model: OwnModel},
-    Borrow {model: BorrowModel}
+pub struct Model {
+    store: Box<dyn ModelStore>
 }
 
 impl Model {
-    pub fn make_borrow(&mut self, lib: std::rc::Rc<Library>, 
+    pub(crate) fn from_store(store: Box<dyn ModelStore>) -> Self {
+        let mut model = Self { store };
+        model.fit_ids();
+        model
+    }
+    pub fn make_borrow(&mut self, core: Arc<dyn MathCore>,
                        points_ref: (*const *mut TBPoint, i32),
                        prims_ref: (*const *mut TPrimitive, i32),
                        nodes_ref: (*const *mut TNode, i32),
                        elems_ref: (*const *mut TElement, i32),
-                       regions_ref: *const *mut TRegion) {
-        *self = Self::Borrow {
-            model: BorrowModel::new (
-                lib, 
-                points_ref,
-                prims_ref, 
-                nodes_ref, 
-                elems_ref,
-                regions_ref
-            )
-        }
+                       regions_ref: (*const *mut TRegion, i32)) {
+        let ids = std::mem::take(self.store.ids_mut());
+        *self = Self::from_store(Box::new(BorrowModel::new (
+            core,
+            points_ref,
+            prims_ref,
+            nodes_ref,
+            elems_ref,
+            regions_ref,
+            ids
+        )))
+    }
+    /// Self-contained copy of the model: points, prims, nodes, elements, regions
+    /// and the field in the elements. Nothing of it points into the core.
+    pub fn detach(&self) -> Self {
+        let fields: Vec<(f64, f64, f64)> = match self.has_field() {
+            true => (0..self.elems_len()).map(|i| self.get_bx_by_bm(i).unwrap_or_default()).collect(),
+            false => vec![]
+        };
+        let has_field = fields.iter().any(|&field| field != (0., 0., 0.));
+        let store = &self.store;
+        let model = OwnModel {
+            points: store.points().to_vec(),
+            prims: store.prims().to_vec(),
+            nodes: store.nodes().iter().map(TNode::detached).collect(),
+            node_links: (0..self.nodes_len()).map(|i| store.node_links(i).to_vec()).collect(),
+            elems: store.elems().to_vec(),
+            regions: store.regions().iter().map(TRegion::detached).collect(),
+            grans: (0..self.regions_len()).map(|i| store.region_gran(i).to_vec()).collect(),
+            fields: if has_field { fields } else { vec![] },
+            ids: store.ids().clone()
+        };
+        Self::from_store(Box::new(model))
     }
+}
+
+impl Model {
+    /// The records live in the math core, which has to stay loaded.
     pub fn is_borrowed(&self) -> bool {
-        match self {
-            Model::Own{..} => false,
-            _ => true
-        }
+        self.store.is_borrowed()
+    }
+    /// Nodes and elements are there.
+    pub fn has_mesh(&self) -> bool {
+        self.store.has_mesh()
+    }
+    /// Bx, By and Bm of the elements can be asked for.
+    pub fn has_field(&self) -> bool {
+        self.store.has_field()
+    }
+    /// Conditions of the prims and parameters of the points and regions can be changed.
+    pub fn editable_physics(&self) -> bool {
+        self.store.editable_physics()
     }
 }
 
 impl Model {
-    pub fn tb_point_ref(&self, num: usize) -> Option<&mut TBPoint> {
-        match self {
-            Model::Borrow{model} => model.tb_point_ref(num),
-            _ => None
-        }
+    /// Persistent ID of the record at `index`, see [super::entity_ids].
+    pub fn id(&self, entity: Entity, index: usize) -> Option<EntityId> {
+        self.store.ids().get(entity, index)
     }
-    pub fn t_primitive_ref(&self, num: usize) -> Option<&mut TPrimitive> {
-        match self {
-            Model::Borrow{model} => model.t_primitive_ref(num),
-            _ => None
-        }
+    /// Gives the record at `index` an ID it had before.
+    pub fn set_id(&mut self, entity: Entity, index: usize, id: EntityId) {
+        self.store.ids_mut().set(entity, index, id)
     }
-    pub fn t_node_ref(&self, num: usize) -> Option<&mut TNode> {
-        match self {
-            Model::Borrow{model} => model.t_node_ref(num),
-            _ => None
-        }
+    /// Gives new IDs to records the core added at the end, e.g. regions or a loaded file.
+    fn fit_ids(&mut self) {
+        let (points, prims, regions) = (self.points_len(), self.prims_len(), self.regions_len());
+        let ids = self.store.ids_mut();
+        ids.fit(Entity::Point, points);
+        ids.fit(Entity::Prim, prims);
+        ids.fit(Entity::Region, regions);
     }
-    
-    pub fn t_region_ref(&self, num: usize) -> Option<&mut TRegion> {
-        match self {
-            Model::Borrow {model} => model.t_region_ref(num),
-            _ => None
-        }
+}
+
+impl Model {
+    pub fn tb_point_ref(&self, num: usize) -> Option<&TBPoint> {
+        self.store.points().get(num)
+    }
+    pub fn t_primitive_ref(&self, num: usize) -> Option<&TPrimitive> {
+        self.store.prims().get(num)
+    }
+    pub fn t_node_ref(&self, num: usize) -> Option<&TNode> {
+        self.store.nodes().get(num)
+    }
+    pub fn t_element_ref(&self, num: usize) -> Option<&TElement> {
+        self.store.elems().get(num)
+    }
+    pub fn t_region_ref(&self, num: usize) -> Option<&TRegion> {
+        self.store.regions().get(num)
+    }
+    /// `NSW` of the node.
+    pub fn node_links(&self, num: usize) -> &[i32] {
+        self.store.node_links(num)
+    }
+}
+
+impl Model {
+    /// Replaces the record. A borrowed model refuses what the core has no call for.
+    pub fn commit_point(&mut self, num: usize, point: &TBPoint) -> Result<(), String> {
+        self.store.commit_point(num, point)
+    }
+    pub fn commit_prim(&mut self, num: usize, prim: &TPrimitive) -> Result<(), String> {
+        self.store.commit_prim(num, prim)
+    }
+    pub fn commit_region(&mut self, num: usize, region: &TRegion) -> Result<(), String> {
+        self.store.commit_region(num, region)
     }
 }
 
 impl Model {
     pub fn points(&self, index: usize) -> Point {
-        match self {
-            Self::Own { model } => model.points[index].0,
-            Self::Borrow { model } => model.get_point(index)
-        }
+        let point = &self.store.points()[index];
+        Point::new(point.x as f32, point.y as f32)
     }
     pub fn points_r(&self, index: usize) -> f32 {
-        match self {
-            Self::Own { model } => model.points[index].1,
-            Self::Borrow { model } => model.get_point_r(index)
-        }
+        self.store.points()[index].r as f32
     }
     pub fn prims(&self, index: usize) -> &[i32; 3] {
-        match self {
-            Self::Own { model } => &model.prims[index],
-            Self::Borrow { model } => model.get_prim(index)
-        }
+        &self.store.prims()[index].p
     }
     pub fn nodes(&self, index: usize) -> Point {
-        match self {
-            Self::Own { model } => model.node_points[index],
-            Self::Borrow { model } => model.node(index)
-        }
+        let node = &self.store.nodes()[index];
+        Point::new(node.x as f32, node.y as f32)
     }
     pub fn elems(&self, index: usize) -> &[i32; 3] {
-        match self {
-            Self::Own { model } => &model.node_lines[index],
-            Self::Borrow { model } => model.elem(index)
-        }
+        &self.store.elems()[index].m
     }
-    pub fn point_set(&mut self, num: usize, point: Point, point_r: f32) {
-        match self {
-            Self::Own { model } => model.points[num] = (point, point_r),
-            Self::Borrow { model } => model.point_set(num, point, point_r)
-        }
+    pub fn point_set(&mut self, num: usize, point: Point, point_r: f32) -> Result<(), String> {
+        self.store.point_set(num, point, point_r)
     }
 
     pub fn points_len(&self) -> usize {
-        match self {
-            Self::Own { model } => model.points.len(),
-            Self::Borrow { model } => model.points_len()
-        }
+        self.store.points().len()
     }
     pub fn prims_len(&self) -> usize {
-        match self {
-            Self::Own { model } => model.prims.len(),
-            Self::Borrow { model } => model.prims_len()
-        }
+        self.store.prims().len()
     }
     pub fn nodes_len(&self) -> usize {
-        match self {
-            Self::Own { model } => model.node_points.len(),
-            Self::Borrow { model } => model.nodes_len()
-        }
+        self.store.nodes().len()
     }
     pub fn elems_len(&self) -> usize {
-        match self {
-            Self::Own { model } => model.node_lines.len(),
-            Self::Borrow { model } => model.elems_len()
-        }
+        self.store.elems().len()
+    }
+    pub fn regions_len(&self) -> usize {
+        self.store.regions().len()
+    }
+    /// `TRegion::Gran`, empty until the mesh is built.
+    pub fn region_gran(&self, index: usize) -> &[TGran] {
+        self.store.region_gran(index)
+    }
+    /// Regions whose boundary goes through the node, with `UgUzl` of the node in each of them.
+    pub fn node_grans(&self, node: usize) -> Vec<(usize, f64)> {
+        (0..self.regions_len())
+            .filter_map(|region| {
+                self.region_gran(region).iter()
+                    .find(|gran| gran.Node as usize == node)
+                    .map(|gran| (region, gran.UgUzl))
+            })
+            .collect()
+    }
+    /// Marker of the region: position and `R`.
+    pub fn region_marker(&self, index: usize) -> Option<(Point, f32)> {
+        self.t_region_ref(index).map(|region| (Point::new(region.x as f32, region.y as f32), region.R as f32))
     }
 }
 
 impl Model {
-    pub fn points_push(&mut self, point: Point, circle: f32) {
-        match self {
-            Self::Own { model } => model.points.push((point, circle)),
-            Self::Borrow { model } => model.points_push(point, circle)
-        }
+    pub fn points_push(&mut self, point: Point, circle: f32) -> Result<(), String> {
+        self.points_insert(self.points_len(), point, circle)
     }
-    pub fn prims_push(&mut self, prim: [i32; 3]) {
-        match self {
-            Self::Own { model } => model.prims.push(prim),
-            Self::Borrow { model } => model.prims_push(prim)
-        }
+    pub fn prims_push(&mut self, prim: [i32; 3]) -> Result<(), String> {
+        self.prims_insert(self.prims_len(), prim)
     }
-    pub fn points_pop(&mut self) {
-        match self {
-            Self::Own { model } => { model.points.pop(); },
-            Self::Borrow { model } => { model.points_pop(); }
+    pub fn points_pop(&mut self) -> Result<(), String> {
+        match self.points_len().checked_sub(1) {
+            Some(last) => self.points_remove(last),
+            None => Ok(())
         }
     }
-    pub fn points_swap(&mut self, a: usize, b: usize) {
-        match self {
-            Self::Own { model } => { model.points.swap(a, b) },
-            Self::Borrow { model } => { model.points_swap(a, b) }
-        }
+    /// Point indices of the prims from `index` on are moved up by one.
+    pub fn points_insert(&mut self, index: usize, point: Point, circle: f32) -> Result<(), String> {
+        self.points_insert_record(index, TBPoint { x: point.x as f64, y: point.y as f64, r: circle as f64, ..Default::default() })
     }
-    pub fn prims_pop(&mut self) {
-        match self {
-            Self::Own { model } => { model.prims.pop(); },
-            Self::Borrow { model } => { model.prims_pop(); }
-        }
+    /// Same as [Model::points_insert], with every field of the point, e.g. a deleted one.
+    pub fn points_insert_record(&mut self, index: usize, record: TBPoint) -> Result<(), String> {
+        self.store.points_insert(index, record)?;
+        self.store.ids_mut().insert(Entity::Point, index);
+        Ok(())
     }
-    pub fn prims_insert(&mut self, index: usize, element: [i32; 3]) {
-        match self {
-            Self::Own { model } => model.prims.insert(index, element),
-            Self::Borrow { model } => model.prims_insert(index, element)
+    /// Point indices of the prims after `index` are moved down by one.
+    /// The prims that use the point have to be deleted before.
+    pub fn points_remove(&mut self, index: usize) -> Result<(), String> {
+        self.store.points_remove(index)?;
+        self.store.ids_mut().remove(Entity::Point, index);
+        Ok(())
+    }
+    pub fn prims_pop(&mut self) -> Result<(), String> {
+        match self.prims_len().checked_sub(1) {
+            Some(last) => self.prims_remove(last),
+            None => Ok(())
         }
     }
+    /// The new prim has the default condition.
+    pub fn prims_insert(&mut self, index: usize, element: [i32; 3]) -> Result<(), String> {
+        self.prims_insert_record(index, TPrimitive { p: element, ..Default::default() })
+    }
+    /// Same as [Model::prims_insert], with every field of the prim, e.g. a deleted one.
+    pub fn prims_insert_record(&mut self, index: usize, record: TPrimitive) -> Result<(), String> {
+        self.store.prims_insert(index, record)?;
+        self.store.ids_mut().insert(Entity::Prim, index);
+        Ok(())
+    }
+    pub fn prims_remove(&mut self, index: usize) -> Result<(), String> {
+        self.store.prims_remove(index)?;
+        self.store.ids_mut().remove(Entity::Prim, index);
+        Ok(())
+    }
 }
 
 impl Model {
-    pub fn get_bm_only(&self, index: i32) -> f32 {
-        match self {
-            Self::Borrow {model} => model.get_bm_only(index),
-            _ => 0.
-        }
+    /// `TypPrim` and `Vp` of the prim, see [super::boundary::Boundary].
+    pub fn prim_condition(&self, index: usize) -> (u8, f64) {
+        self.t_primitive_ref(index)
+            .map(|prim| (prim.TypPrim, prim.Vp))
+            .unwrap_or_default()
+    }
+    pub fn set_prim_condition(&mut self, index: usize, typ: u8, vp: f64) -> Result<(), String> {
+        let mut prim = self.t_primitive_ref(index).cloned().ok_or_else(|| format!("No prim {index}"))?;
+        prim.TypPrim = typ;
+        prim.Vp = vp;
+        self.commit_prim(index, &prim)
+    }
+}
+
+impl Model {
+    /// Bm of the element, `None` without a field.
+    pub fn get_bm_only(&self, index: usize) -> Option<f32> {
+        self.store.bm(index)
+    }
+    /// Bx, By and Bm of the element, `None` without a field.
+    pub fn get_bx_by_bm(&self, index: usize) -> Option<(f64, f64, f64)> {
+        self.store.field(index)
     }
 }
 
 impl Model {
     pub fn clear(&mut self) {
-        match self {
-            Self::Own {model} => model.clear(), 
-            Self::Borrow {model} => model.clear()
-        };
+        self.store.clear();
+        self.store.ids_mut().clear()
     }
     pub fn find_point(&self, point: Point, scale: f32, zoom_scale: f32) -> usize {
-        match self {
-            Self::Own {model} => model.find_point(point, scale, zoom_scale), 
-            Self::Borrow {model} => model.find_point(point, scale, zoom_scale)
-        }
+        (0..self.points_len())
+            .position(|index| self.points(index).distance(point) < scale / zoom_scale * 2.0)
+            .unwrap_or(self.points_len())
     }
-    pub fn find_min_max(&self) -> (Point, Point) {
-        match self {
-            Self::Own {model} => model.find_min_max(),
-            Self::Borrow {model} => model.find_min_max()
+    /// Nearest line or arc closer than the point pick distance.
+    pub fn find_prim(&self, point: Point, scale: f32, zoom_scale: f32) -> Option<usize> {
+        let distance = |index: usize| {
+            let prim = self.prims(index);
+            if prim[2] == -1 {
+                distance_to_segment(point, self.points(prim[0] as usize), self.points(prim[1] as usize))
+            } else {
+                let (center, radius, start, end) = self.arc_params(prim);
+                let mut angle = (point.y - center.y).atan2(point.x - center.x);
+                if angle < start {
+                    angle += std::f32::consts::TAU
+                }
+                if angle <= end {
+                    (point.distance(center) - radius).abs()
+                } else {
+                    point.distance(self.points(prim[0] as usize)).min(point.distance(self.points(prim[1] as usize)))
+                }
+            }
+        };
+        (0..self.prims_len())
+            .filter(|&index| prim_fits(self.prims(index), self.points_len()))
+            .map(|index| (index, distance(index)))
+            .filter(|&(_, distance)| distance < scale / zoom_scale * 2.0)
+            .min_by(|a, b| a.1.total_cmp(&b.1))
+            .map(|(index, _)| index)
+    }
+    /// Nearest node closer than the point pick distance.
+    pub fn find_node(&self, point: Point, scale: f32, zoom_scale: f32) -> Option<usize> {
+        (0..self.nodes_len())
+            .map(|index| (index, point.distance(self.nodes(index))))
+            .filter(|&(_, distance)| distance < scale / zoom_scale * 2.0)
+            .min_by(|a, b| a.1.total_cmp(&b.1))
+            .map(|(index, _)| index)
+    }
+    /// `IZP` of the element under the point, without a mesh the region whose marker circle covers it.
+    pub fn find_region(&self, point: Point) -> Option<usize> {
+        let inside = |elem: &[i32; 3]| {
+            let [a, b, c] = elem.map(|node| self.nodes(node as usize));
+            let side = |p: Point, q: Point| (q.x - p.x) * (point.y - p.y) - (q.y - p.y) * (point.x - p.x);
+            let sides = [side(a, b), side(b, c), side(c, a)];
+            sides.iter().all(|&s| s >= 0.) || sides.iter().all(|&s| s <= 0.)
+        };
+        let by_elem = (0..self.elems_len())
+            .filter_map(|index| self.t_element_ref(index))
+            .filter(|elem| elem_fits(&elem.m, self.nodes_len()))
+            .find(|elem| inside(&elem.m))
+            .and_then(|elem| usize::try_from(elem.IZP).ok())
+            .filter(|&region| region < self.regions_len());
+        by_elem.or_else(|| {
+            (0..self.regions_len()).find(|&index| {
+                self.region_marker(index).is_some_and(|(center, radius)| point.distance(center) <= radius)
+            })
+        })
+    }
+    /// Center, radius, start and end angle of an arc prim. The third point is the center,
+    /// the arc goes counterclockwise from the first point to the angle of the second one.
+    pub fn arc_params(&self, prim: &[i32; 3]) -> (Point, f32, f32, f32) {
+        let start = self.points(prim[0] as usize);
+        let end = self.points(prim[1] as usize);
+        let center = self.points(prim[2] as usize);
+        let start_angle = (start.y - center.y).atan2(start.x - center.x);
+        let mut end_angle = (end.y - center.y).atan2(end.x - center.x);
+        if end_angle <= start_angle {
+            end_angle += std::f32::consts::TAU
         }
+        (center, start.distance(center), start_angle, end_angle)
     }
-    pub fn replace_prim(&mut self, one: i32, two: i32) {
-        match self {
-            Self::Own {model} => model.replace_prim(one, two), 
-            Self::Borrow {model} => model.replace_prim(one, two)
+    /// Bounds of the points and the nodes, so a mesh without geometry is found too.
+    pub fn find_min_max(&self) -> (Point, Point) {
+        let mut all = (0..self.points_len()).map(|index| self.points(index))
+            .chain((0..self.nodes_len()).map(|index| self.nodes(index)));
+        let Some(first) = all.next() else {
+            return (Point::new(0., 0.), Point::new(1000., 1000.))
+        };
+        let mut min = first;
+        let mut max = min;
+        for point in all {
+            min.x = min.x.min(point.x);
+            min.y = min.y.min(point.y);
+            max.x = max.x.max(point.x);
+            max.y = max.y.max(point.y);
         }
+        (min, max)
     }
-    pub fn prims_retain_safe<F>(&mut self, f: F, journal: &mut UndoManager) 
+    /// Deletes from the end, so the indices of the prims left to delete stay the same.
+    /// Stops at the first prim that is not deleted, the ones deleted before are in the journal.
+    pub fn prims_retain_safe<F>(&mut self, mut f: F, journal: &mut UndoManager) -> Result<(), String>
     where
         F: FnMut(&[i32; 3]) -> bool
     {
-        match self {
-            Self::Own { model } => model.prims_retain_safe(f, journal),
-            Self::Borrow { model } => model.prims_retain_safe(f, journal)
+        let deleted: Vec<usize> = (0..self.prims_len())
+            .rev()
+            .filter(|&placement| !f(self.prims(placement)))
+            .collect();
+        for placement in deleted {
+            let Some(record) = self.t_primitive_ref(placement).cloned() else {
+                continue
+            };
+            let id = self.id(Entity::Prim, placement);
+            self.prims_remove(placement)?;
+            journal.deleted_prim(placement, record, id);
         }
+        Ok(())
     }
 
     pub fn sync_everything(&mut self) {
-        match self {
-            Model::Borrow {model} => {
-                model.sync_everything();
-            },
-            _ => {}
-        }
+        self.store.sync();
+        self.fit_ids()
     }
+}
 
+fn distance_to_segment(point: Point, a: Point, b: Point) -> f32 {
+    let ab = b - a;
+    let len = ab.x * ab.x + ab.y * ab.y;
+    let t = if len == 0. { 0. } else { (((point.x - a.x) * ab.x + (point.y - a.y) * ab.y) / len).clamp(0., 1.) };
+    point.distance(a + ab * t)
 }
 
 impl Default for Model {
     fn default() -> Self {
-        Self::Own {model: OwnModel::default()}
+        Self::from_store(Box::new(OwnModel::default()))
     }
 }
@@ -1,28 +1,28 @@
-use std::slice::from_raw_parts;
 use iced::widget::canvas;
-use iced::widget::canvas::{Path, Stroke, path::Arc};
+use iced::widget::canvas::{Path, path::Arc};
 use crate::app_config::app_config::{AppConfig, NodeMode};
 use crate::app_config::zoom::Zoom;
 use super::model::*;
+use super::boundary::Boundary;
+use super::painter::Painter;
+use super::validate::{elem_fits, prim_fits};
 
 impl Model {
-    pub fn draw_model(&self, frame: &mut canvas::Frame, scale: f32, app_config: &AppConfig) {
+    pub fn draw_model(&self, frame: &mut impl Painter, scale: f32, app_config: &AppConfig) {
         if app_config.prims_show {
             let prim_color = app_config.get_color("Prims");
-            let lines = Path::new(|p| {
-                for index in 0..self.prims_len() {
-                    let prim = self.prims(index);
-                    if prim[2] == -1 {
-                        if app_config.is_line_inside(self.points(prim[0] as usize), self.points(prim[1] as usize)){
-                            p.move_to(app_config.zoom.apply(self.points(prim[0] as usize)));
-                            p.line_to(app_config.zoom.apply(self.points(prim[1] as usize)));
+            // the known conditions in their colors, the rest in the color of the prims
+            let colors = Boundary::ALL.iter().map(|boundary| boundary.color()).chain([None]);
+            for color in colors {
+                let lines = Path::new(|p| {
+                    for index in 0..self.prims_len() {
+                        if Boundary::from_byte(self.prim_condition(index).0).color() == color {
+                            self.prim_path(p, index, app_config)
                         }
-                    } else {
-                        //self.approx_arc(p, prim, &app_config.zoom)
                     }
-                }
-            });
-            frame.stroke(&lines, Stroke::default().with_color(prim_color).with_width(scale));
+                });
+                frame.stroke(&lines, color.unwrap_or(prim_color), scale);
+            }
         }
         
         if app_config.points_show || app_config.circles_show {
@@ -30,22 +30,34 @@ impl Model {
             let circle_color = app_config.get_color("Circles");
             for index in 0..self.points_len() {
                 let point = self.points(index);
-                if app_config.points_show {
-                    if app_config.is_point_inside(point, scale * 2.) {
-                        let point_draw = Path::circle(app_config.zoom.apply(point), scale * 2.);
-                        frame.fill(&point_draw, point_color);
-                    }
+                if app_config.points_show && app_config.is_point_inside(point, scale * 2.) {
+                    let point_draw = Path::circle(app_config.zoom.apply(point), scale * 2.);
+                    frame.fill(&point_draw, point_color);
                 }
-                if app_config.circles_show {
-                    if app_config.is_point_inside(point, self.points_r(index)) {
-                        let dot = Path::circle(app_config.zoom.apply(point), self.points_r(index) * app_config.zoom.scale);
-                        frame.stroke(&dot, Stroke::default().with_color(circle_color).with_width(2.0))
-                    }
+                if app_config.circles_show && app_config.is_point_inside(point, self.points_r(index)) {
+                    let dot = Path::circle(app_config.zoom.apply(point), self.points_r(index) * app_config.zoom.scale);
+                    frame.stroke(&dot, circle_color, 2.0)
                 }
             };
         }
 
-        self.draw_nodes(app_config, frame, scale);
+        if app_config.gran_show {
+            for index in 0..self.regions_len() {
+                self.draw_gran(frame, index, scale, app_config);
+            }
+        }
+
+        if app_config.regions_show {
+            let region_color = app_config.get_color("Regions");
+            for index in 0..self.regions_len() {
+                let color = if app_config.gran_show { region_color_of(index) } else { region_color };
+                self.draw_region(frame, index, color, scale, app_config);
+            }
+        }
+
+        if self.has_mesh() {
+            self.draw_nodes(app_config, frame, scale);
+        }
         
         if app_config.node_points_show {
             let node_point_color = app_config.get_color("Node points");
@@ -62,20 +74,97 @@ impl Model {
         }
     }
 
-    #[allow(dead_code)]
-    /// Must be rewritten!
+    /// Highlights the prims chosen for a boundary condition.
+    pub fn draw_chosen_prims(&self, frame: &mut impl Painter, chosen: &[usize], scale: f32, app_config: &AppConfig) {
+        let lines = Path::new(|p| {
+            for &index in chosen.iter().filter(|&&index| index < self.prims_len()) {
+                self.prim_path(p, index, app_config)
+            }
+        });
+        frame.stroke(&lines, iced::Color::from_rgb8(255, 0, 0), scale * 2.0);
+    }
+
+    /// Highlights the region chosen in the region list or with "Find".
+    pub fn draw_chosen_region(&self, frame: &mut impl Painter, chosen: Option<usize>, scale: f32, app_config: &AppConfig) {
+        if let Some(index) = chosen {
+            self.draw_region(frame, index, iced::Color::from_rgb8(255, 0, 0), scale, app_config)
+        }
+    }
+
+    /// Marker of the region: a cross in (x, y) and a circle of radius R.
+    fn draw_region(&self, frame: &mut impl Painter, index: usize, color: iced::Color, scale: f32, app_config: &AppConfig) {
+        let Some((center, radius)) = self.region_marker(index) else {
+            return
+        };
+        if !app_config.is_point_inside(center, radius) {
+            return
+        }
+        let center = app_config.zoom.apply(center);
+        let arm = scale * 4.0;
+        let marker = Path::new(|p| {
+            p.move_to(center - iced::Vector::new(arm, 0.));
+            p.line_to(center + iced::Vector::new(arm, 0.));
+            p.move_to(center - iced::Vector::new(0., arm));
+            p.line_to(center + iced::Vector::new(0., arm));
+            p.circle(center, radius * app_config.zoom.scale);
+        });
+        frame.stroke(&marker, color, scale);
+    }
+
+    /// Closed contour through the `Gran` nodes of the region.
+    fn draw_gran(&self, frame: &mut impl Painter, index: usize, scale: f32, app_config: &AppConfig) {
+        let nodes: Vec<_> = self.region_gran(index).iter()
+            .filter(|gran| gran.Node >= 0 && (gran.Node as usize) < self.nodes_len())
+            .map(|gran| app_config.zoom.apply(self.nodes(gran.Node as usize)))
+            .collect();
+        let Some(&first) = nodes.first() else {
+            return
+        };
+        let contour = Path::new(|p| {
+            p.move_to(first);
+            for &node in &nodes[1..] {
+                p.line_to(node);
+            }
+            p.close();
+        });
+        frame.stroke(&contour, region_color_of(index), scale * 1.5);
+    }
+
+    /// Adds a visible line or arc to `p`. A prim with missing points is skipped.
+    fn prim_path(&self, p: &mut canvas::path::Builder, index: usize, app_config: &AppConfig) {
+        let prim = self.prims(index);
+        if !prim_fits(prim, self.points_len()) {
+            return
+        }
+        if prim[2] == -1 {
+            if app_config.is_line_inside(self.points(prim[0] as usize), self.points(prim[1] as usize)){
+                p.move_to(app_config.zoom.apply(self.points(prim[0] as usize)));
+                p.line_to(app_config.zoom.apply(self.points(prim[1] as usize)));
+            }
+        } else {
+            let (center, radius, _, _) = self.arc_params(prim);
+            if app_config.is_point_inside(center, radius) {
+                self.approx_arc(p, prim, &app_config.zoom)
+            }
+        }
+    }
+
     fn approx_arc (&self, p: &mut canvas::path::Builder, prim: &[i32; 3], zoom: &Zoom) {
+        let (center, radius, start_angle, end_angle) = self.arc_params(prim);
         let arc = Arc{
-            center: zoom.apply(self.points(prim[2] as usize)),
-            radius: 123.,
-            start_angle: iced::Radians(0.0),
-            end_angle: iced::Radians(360.0)
+            center: zoom.apply(center),
+            radius: radius * zoom.scale,
+            start_angle: iced::Radians(start_angle),
+            end_angle: iced::Radians(end_angle)
         };
         p.arc(arc);
     }
 
-    fn draw_nodes (&self, app_config: &AppConfig, frame: &mut canvas::Frame, scale: f32) {
+    fn draw_nodes (&self, app_config: &AppConfig, frame: &mut impl Painter, scale: f32) {
         let triangle_path = |elem: &[i32; 3]| -> Option<Path> {
+            if !elem_fits(elem, self.nodes_len()) {
+                return None
+            }
             let is_visible =
                 app_config.is_line_inside(self.nodes(elem[0] as usize), self.nodes(elem[1] as usize)) ||
                     app_config.is_line_inside(self.nodes(elem[0] as usize), self.nodes(elem[2] as usize)) ||
@@ -99,24 +188,24 @@ impl Model {
                     let elem = self.elems(index);
 
                     if let Some(triangle) = triangle_path(elem) {
-                        frame.stroke(&triangle, Stroke::default().with_color(node_line_color).with_width(scale / 2.0))
+                        frame.stroke(&triangle, node_line_color, scale / 2.0)
                     }
                 }
             }
 
             NodeMode::Green { max } => {
-                if self.is_borrowed() {
+                if self.has_field() {
                     for index in 0..self.elems_len() {
                         let elem = self.elems(index);
 
                         if let Some(triangle) = triangle_path(elem) {
-                            let current_green = self.get_bm_only(index as i32);
+                            let current_green = self.get_bm_only(index).unwrap_or_default();
                             frame.fill(&triangle, iced::Color::from_rgb(0.0, current_green / max, 0.0));
                         }
                     }
                 }
                 else {
-                    println!("Model needs to be borrowed! Try sending it.")
+                    println!("Model has no field! Try sending it and building the mesh.")
                 }
             }
 
@@ -124,20 +213,35 @@ impl Model {
         }
     }
     
-    fn draw_approx(&self, app_config: &AppConfig, frame: &mut canvas::Frame, scale: f32, index: usize) {
-        if let Some(node) = self.t_node_ref(index) {
-            let start = app_config.zoom.apply(self.nodes(index));
-            let end_indexes = unsafe { from_raw_parts(node.NSW, node.KolSW as usize) };
+    fn draw_approx(&self, app_config: &AppConfig, frame: &mut impl Painter, scale: f32, index: usize) {
+        let end_indexes = self.node_links(index);
+        if !end_indexes.is_empty() {
+            let start = self.nodes(index);
             let color = app_config.get_color("Node lines");
             for &end_inx in end_indexes {
-                if end_inx < index as i32 {
-                    let end = app_config.zoom.apply(self.nodes(end_inx as usize));
+                if end_inx >= 0 && end_inx < index as i32 {
+                    let end = self.nodes(end_inx as usize);
                     if app_config.is_line_inside(start, end) {
-                        let new_line = Path::line(start, end);
-                        frame.stroke(&new_line, Stroke::default().with_color(color).with_width(scale/2.0))
+                        let new_line = Path::line(app_config.zoom.apply(start), app_config.zoom.apply(end));
+                        frame.stroke(&new_line, color, scale/2.0)
                     }
                 }
             }
         }
     }
 }
+
+/// Color of the region in "Region boundaries" view: hues are spread by the golden angle.
+pub fn region_color_of(index: usize) -> iced::Color {
+    let hue = (index as f32 * 137.508) % 360.0 / 60.0;
+    let x = 1.0 - (hue % 2.0 - 1.0).abs();
+    let (r, g, b) = match hue as u32 {
+        0 => (1.0, x, 0.0),
+        1 => (x, 1.0, 0.0),
+        2 => (0.0, 1.0, x),
+        3 => (0.0, x, 1.0),
+        4 => (x, 0.0, 1.0),
+        _ => (1.0, 0.0, x)
+    };
+    iced::Color::from_rgb(r * 0.8, g * 0.8, b * 0.8)
+}
@@ -1,85 +1,138 @@
 use iced::Point;
-use crate::app::undo_manager::UndoManager;
+use super::borrow_types::*;
+use super::entity_ids::EntityIds;
+use super::store::ModelStore;
+use super::validate::elem_fits;
 
+/// Model in the memory of the editor. It keeps the same records as the core,
+/// so a detached model still has its mesh, regions and field.
 #[derive(Debug, Default)]
-pub(super) struct OwnModel {
-    pub(super) points: Vec<(Point, f32)>,
-    pub(super) prims: Vec<[i32; 3]>,
-    pub(super) node_points: Vec<Point>,
-    pub(super) node_lines: Vec<[i32; 3]>
+pub(crate) struct OwnModel {
+    pub(super) points: Vec<TBPoint>,
+    pub(super) prims: Vec<TPrimitive>,
+    /// `NSW` of the nodes is null, the links are in `node_links`.
+    pub(super) nodes: Vec<TNode>,
+    pub(super) node_links: Vec<Vec<i32>>,
+    pub(super) elems: Vec<TElement>,
+    /// `Gran` of the regions is null, the boundaries are in `grans`.
+    pub(super) regions: Vec<TRegion>,
+    pub(super) grans: Vec<Vec<TGran>>,
+    /// Bx, By and Bm of every element, empty if there is no field.
+    pub(super) fields: Vec<(f64, f64, f64)>,
+    pub(super) ids: EntityIds
 }
 
 impl OwnModel {
-    pub(super) fn find_point(&self, point: Point, scale: f32, zoom_scale: f32) -> usize {
-        self.points
-            .iter()
-            .position(|x| { x.0.distance(point) < scale / zoom_scale * 2.0 })
-            .unwrap_or(self.points.len())
-    }
-
-    pub(super) fn find_min_max(&self) -> (Point, Point) {
-        if let Some(min) = self.points.get(0) {
-            let mut min = min.0;
-            let mut max = min;
-
-            for (point, _) in &self.points {
-                min.x = min.x.min(point.x);
-                min.y = min.y.min(point.y);
-                max.x = max.x.max(point.x);
-                max.y = max.y.max(point.y);
+    /// Fills `node_links` and `KolSW` from the edges of the elements, for a mesh read without the core.
+    pub(super) fn link_nodes(&mut self) {
+        let mut links = vec![vec![]; self.nodes.len()];
+        for elem in self.elems.iter().filter(|elem| elem_fits(&elem.m, self.nodes.len())) {
+            for (a, b) in [(0, 1), (1, 2), (2, 0)] {
+                let (a, b) = (elem.m[a], elem.m[b]);
+                for (from, to) in [(a, b), (b, a)] {
+                    if !links[from as usize].contains(&to) {
+                        links[from as usize].push(to)
+                    }
+                }
             }
-
-            (min, max)
         }
-        else {
-            (Point::new(0., 0.), Point::new(1000., 1000.))
+        for (node, node_links) in self.nodes.iter_mut().zip(&links) {
+            node.KolSW = node_links.len() as i32
         }
+        self.node_links = links;
     }
+}
 
-    pub(super) fn replace_prim(&mut self, one: i32, two: i32) {
-        self.prims.iter_mut().for_each(|x|{
-            if x[0] == one {
-                x[0] = two
-            } else if x[0] == two {
-                x[0] = one
-            }
+impl ModelStore for OwnModel {
+    fn points(&self) -> &[TBPoint] { &self.points }
+    fn prims(&self) -> &[TPrimitive] { &self.prims }
+    fn nodes(&self) -> &[TNode] { &self.nodes }
+    fn node_links(&self, index: usize) -> &[i32] {
+        self.node_links.get(index).map_or(&[], Vec::as_slice)
+    }
+    fn elems(&self) -> &[TElement] { &self.elems }
+    fn regions(&self) -> &[TRegion] { &self.regions }
+    fn region_gran(&self, index: usize) -> &[TGran] {
+        self.grans.get(index).map_or(&[], Vec::as_slice)
+    }
+    fn field(&self, index: usize) -> Option<(f64, f64, f64)> {
+        self.fields.get(index).copied()
+    }
 
-            if x[1] == one {
-                x[1] = two
-            } else if x[1] == two {
-                x[1] = one
-            }
+    fn ids(&self) -> &EntityIds { &self.ids }
+    fn ids_mut(&mut self) -> &mut EntityIds { &mut self.ids }
 
-            if x[2] == one {
-                x[2] = two
-            } else if x[2] == two {
-                x[2] = one
-            }
-        })
+    fn has_field(&self) -> bool {
+        !self.fields.is_empty()
     }
-    
-    pub(super) fn prims_retain_safe<F>(&mut self, mut f: F, journal: &mut UndoManager) 
-    where
-        F: FnMut(&[i32; 3]) -> bool
-    {
-        self.prims
-            .iter()
-            .enumerate()
-            .rev()
-            .for_each(|(placement, x)| {
-                if !f(x) {
-                    journal.deleted_prim(placement, x.clone())
-                }
-            });
-        self.prims.retain(f);
+
+    fn commit_point(&mut self, index: usize, point: &TBPoint) -> Result<(), String> {
+        let old = self.points.get_mut(index).ok_or_else(|| format!("No point {index}"))?;
+        *old = point.clone();
+        Ok(())
+    }
+    fn commit_prim(&mut self, index: usize, prim: &TPrimitive) -> Result<(), String> {
+        let old = self.prims.get_mut(index).ok_or_else(|| format!("No prim {index}"))?;
+        *old = prim.clone();
+        Ok(())
+    }
+    fn commit_region(&mut self, index: usize, region: &TRegion) -> Result<(), String> {
+        let old = self.regions.get_mut(index).ok_or_else(|| format!("No region {index}"))?;
+        *old = region.detached();
+        Ok(())
+    }
+    fn point_set(&mut self, index: usize, point: Point, point_r: f32) -> Result<(), String> {
+        let record = self.points.get_mut(index).ok_or_else(|| format!("No point {index}"))?;
+        (record.x, record.y, record.r) = (point.x as f64, point.y as f64, point_r as f64);
+        Ok(())
+    }
+
+    fn points_insert(&mut self, index: usize, record: TBPoint) -> Result<(), String> {
+        let len = self.points.len();
+        if index > len {
+            return Err(format!("No place {index} for a point"))
+        }
+        self.points.insert(index, record);
+        if index < len {
+            self.prims.iter_mut().for_each(|prim| shift_point_refs(&mut prim.p, index as i32, 1));
+        }
+        Ok(())
+    }
+    fn points_remove(&mut self, index: usize) -> Result<(), String> {
+        if index >= self.points.len() {
+            return Err(format!("No point {index}"))
+        }
+        self.points.remove(index);
+        self.prims.iter_mut().for_each(|prim| shift_point_refs(&mut prim.p, index as i32 + 1, -1));
+        Ok(())
+    }
+    fn prims_insert(&mut self, index: usize, record: TPrimitive) -> Result<(), String> {
+        if index > self.prims.len() {
+            return Err(format!("No place {index} for a prim"))
+        }
+        self.prims.insert(index, record);
+        Ok(())
+    }
+    fn prims_remove(&mut self, index: usize) -> Result<(), String> {
+        if index >= self.prims.len() {
+            return Err(format!("No prim {index}"))
+        }
+        self.prims.remove(index);
+        Ok(())
+    }
+
+    fn clear(&mut self) {
+        let ids = std::mem::take(&mut self.ids);
+        *self = Self { ids, ..Self::default() };
     }
 }
 
-impl OwnModel {
-    pub(super) fn clear(&mut self) {
-        self.points.clear();
-        self.prims.clear();
-        self.node_points.clear();
-        self.node_lines.clear();
+/// Moves every point index of the prim starting from `from` by `by`.
+/// Both models remap the prims this way when a point is inserted or removed.
+pub(super) fn shift_point_refs(prim: &mut [i32; 3], from: i32, by: i32) {
+    for p in prim.iter_mut() {
+        if *p >= from {
+            *p += by
+        }
     }
-}
\ No newline at end of file
+}
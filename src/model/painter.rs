@@ -0,0 +1,84 @@
+//! Targets of [Model::draw_model]. The canvas frame and the file exports get the same paths, colors and widths.
+
+use iced::{Color, Point, Size, Vector};
+use iced::widget::canvas::{self, Path, Stroke};
+use crate::app_config::AppConfig;
+use super::Model;
+
+pub trait Painter {
+    fn stroke(&mut self, path: &Path, color: Color, width: f32);
+    fn fill(&mut self, path: &Path, color: Color);
+}
+
+impl Painter for canvas::Frame {
+    fn stroke(&mut self, path: &Path, color: Color, width: f32) {
+        canvas::Frame::stroke(self, path, Stroke::default().with_color(color).with_width(width))
+    }
+    fn fill(&mut self, path: &Path, color: Color) {
+        canvas::Frame::fill(self, path, color)
+    }
+}
+
+/// Bounds of the points (with their circles if they are shown), the nodes and the region markers.
+fn model_bounds(model: &Model, app_config: &AppConfig) -> Option<(Point, Point)> {
+    let mut bounds: Option<(Point, Point)> = None;
+    let mut include = |point: Point, reach: f32| {
+        if !(point.x.is_finite() && point.y.is_finite()) {
+            return
+        }
+        let (min, max) = bounds.get_or_insert((point, point));
+        (min.x, min.y) = (min.x.min(point.x - reach), min.y.min(point.y - reach));
+        (max.x, max.y) = (max.x.max(point.x + reach), max.y.max(point.y + reach));
+    };
+    for index in 0..model.points_len() {
+        include(model.points(index), if app_config.circles_show { model.points_r(index) } else { 0. })
+    }
+    for index in 0..model.nodes_len() {
+        include(model.nodes(index), 0.)
+    }
+    for index in 0..model.regions_len() {
+        if let Some((center, radius)) = model.region_marker(index) {
+            include(center, radius)
+        }
+    }
+    bounds
+}
+
+/// Copy of the view of `size` pixels with the zoom that fits `min`..`max` into it, centered.
+pub fn fit_view(app_config: &AppConfig, min: Point, max: Point, size: Size) -> AppConfig {
+    let mut view = AppConfig::default();
+    view.apply_view_state(&app_config.view_state());
+    view.model_size = size;
+
+    let fit = |pixels: f32, length: f32| if length > 0. { pixels / length } else { f32::INFINITY };
+    let scale = fit(size.width, max.x - min.x).min(fit(size.height, max.y - min.y));
+    if scale.is_finite() && scale > 0. {
+        view.zoom.scale = scale
+    }
+    let center = Point::new((min.x + max.x) / 2., (min.y + max.y) / 2.);
+    view.zoom.shift = Vector::new(center.x - size.width / 2. / view.zoom.scale, center.y - size.height / 2. / view.zoom.scale);
+    view
+}
+
+/// [model_bounds] with a margin of 2% of the longer side, the origin for an empty model.
+pub fn whole_model_bounds(model: &Model, app_config: &AppConfig) -> (Point, Point) {
+    let Some((min, max)) = model_bounds(model, app_config) else {
+        return (Point::ORIGIN, Point::ORIGIN)
+    };
+    let span = (max.x - min.x).max(max.y - min.y);
+    let margin = if span > 0. { span * 0.02 } else { 1. };
+    let margin = Vector::new(margin, margin);
+    (min - margin, max + margin)
+}
+
+/// Copy of the view with the zoom and size that show the whole model.
+/// The longer side of the picture is `side` pixels, the other one follows the proportions of the model.
+pub fn whole_model_view(model: &Model, app_config: &AppConfig, side: f32) -> AppConfig {
+    let (min, max) = whole_model_bounds(model, app_config);
+    let (width, height) = (max.x - min.x, max.y - min.y);
+    if width.max(height) <= 0. {
+        return fit_view(app_config, min, max, app_config.model_size)
+    }
+    let scale = side / width.max(height);
+    fit_view(app_config, min, max, Size::new(width * scale, height * scale))
+}
@@ -0,0 +1,69 @@
+//! PNG rendering on the CPU: [Model::draw_model] paints into a tiny-skia pixmap, no window or GPU is needed.
+
+use iced::Color;
+use iced::widget::canvas::Path;
+use iced::widget::canvas::path::lyon_path::Event;
+use tiny_skia::{FillRule, Paint, PathBuilder, Pixmap, Stroke, Transform};
+use crate::app_config::AppConfig;
+use super::painter::Painter;
+use super::Model;
+
+pub const PNG_EXTENSION: &str = "png";
+
+/// The path ends with [PNG_EXTENSION].
+pub fn is_png(path: &str) -> bool {
+    path.trim().rsplit_once('.').is_some_and(|(_, extension)| extension.eq_ignore_ascii_case(PNG_EXTENSION))
+}
+
+struct PngPainter {
+    pixmap: Pixmap
+}
+
+/// The same curves as on the canvas, `None` for an empty path.
+fn skia_path(path: &Path) -> Option<tiny_skia::Path> {
+    let mut builder = PathBuilder::new();
+    for event in path.raw().iter() {
+        match event {
+            Event::Begin { at } => builder.move_to(at.x, at.y),
+            Event::Line { to, .. } => builder.line_to(to.x, to.y),
+            Event::Quadratic { ctrl, to, .. } => builder.quad_to(ctrl.x, ctrl.y, to.x, to.y),
+            Event::Cubic { ctrl1, ctrl2, to, .. } => builder.cubic_to(ctrl1.x, ctrl1.y, ctrl2.x, ctrl2.y, to.x, to.y),
+            Event::End { close: true, .. } => builder.close(),
+            Event::End { close: false, .. } => {}
+        }
+    }
+    builder.finish()
+}
+
+fn paint(color: Color) -> Paint<'static> {
+    let [r, g, b, a] = color.into_rgba8();
+    let mut paint = Paint::default();
+    paint.set_color_rgba8(r, g, b, a);
+    paint.anti_alias = true;
+    paint
+}
+
+impl Painter for PngPainter {
+    fn stroke(&mut self, path: &Path, color: Color, width: f32) {
+        if let Some(path) = skia_path(path) {
+            let stroke = Stroke { width, ..Stroke::default() };
+            self.pixmap.stroke_path(&path, &paint(color), &stroke, Transform::identity(), None)
+        }
+    }
+    fn fill(&mut self, path: &Path, color: Color) {
+        if let Some(path) = skia_path(path) {
+            self.pixmap.fill_path(&path, &paint(color), FillRule::Winding, Transform::identity(), None)
+        }
+    }
+}
+
+/// Renders what the canvas would show with `app_config` on a white background, `model_size` is the resolution.
+pub fn save_png(path: &str, model: &Model, scale: f32, app_config: &AppConfig) -> Result<(), String> {
+    let (width, height) = (app_config.model_size.width.round() as u32, app_config.model_size.height.round() as u32);
+    let mut pixmap = Pixmap::new(width, height).ok_or_else(|| format!("No picture of {width}x{height} pixels"))?;
+    pixmap.fill(tiny_skia::Color::WHITE);
+
+    let mut painter = PngPainter { pixmap };
+    model.draw_model(&mut painter, scale, app_config);
+    painter.pixmap.save_png(path.trim()).map_err(|error| format!("{}: {error}", path.trim()))
+}
@@ -0,0 +1,382 @@
+//! Native project file: the whole model with every field of the records, the IDs and the view, as JSON.
+//!
+//! Unlike CSV it keeps the regions, materials, boundary conditions, the mesh and the field,
+//! and unlike `.mke` it does not need the math core. Every file has a schema version;
+//! older files go through [MIGRATIONS] before they are read, newer ones are refused.
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use serde_json::Value;
+use crate::app_config::app_config::{AppConfig, ViewState};
+use super::borrow_types::*;
+use super::entity_ids::{Entity, EntityId};
+use super::own_model::OwnModel;
+use super::Model;
+
+pub const PROJECT_EXTENSION: &str = "vrp";
+/// Version written to new files.
+pub const PROJECT_VERSION: u64 = 1;
+
+/// Turns a file of one version into the next one.
+type Migration = fn(&mut Value) -> Result<(), String>;
+
+/// `MIGRATIONS[v - 1]` turns a file of version `v` into version `v + 1`.
+/// A change that renames or moves fields adds a step here, a new field with a default does not need one.
+const MIGRATIONS: [Migration; PROJECT_VERSION as usize - 1] = [];
+
+#[derive(Serialize, Deserialize)]
+#[serde(remote = "TBPoint")]
+#[allow(non_snake_case)]
+struct TBPointDef {
+    x: f64,
+    y: f64,
+    r: f64,
+    TypPoint: u8,
+    Vp: f64,
+    Ip: f64,
+    NNode: i32
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(remote = "TPrimitive")]
+#[allow(non_snake_case)]
+struct TPrimitiveDef {
+    p: [i32; 3],
+    TypPrim: u8,
+    IsFront: bool,
+    Vp: f64,
+    Ip: f64
+}
+
+/// The arrays of the node are not kept, only `NSW` goes to [NodeEntry::links].
+#[derive(Serialize, Deserialize)]
+#[serde(remote = "TNode")]
+#[allow(non_snake_case)]
+struct TNodeDef {
+    x: f64,
+    y: f64,
+    VP: f64,
+    TypNode: u8,
+    PNode: i32,
+    KolSW: i32,
+    #[serde(skip, default = "std::ptr::null_mut")]
+    NSW: *mut i32,
+    KolSI: i32,
+    #[serde(skip, default = "std::ptr::null_mut")]
+    NSI: *mut i32,
+    #[serde(skip, default = "std::ptr::null_mut")]
+    NSK: *mut i8,
+    vKolSWMemo: i32,
+    vKolSIMemo: i32,
+    F: f64,
+    Yp: f64
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(remote = "TElement")]
+#[allow(non_snake_case)]
+struct TElementDef {
+    m: [i32; 3],
+    IZP: i16,
+    Px: f64,
+    Py: f64,
+    XNJU: f64,
+    A1: f64,
+    Delta: f64,
+    xs: f64,
+    ys: f64,
+    S: [f64; 6],
+    A: [f64; 3],
+    B: [f64; 3],
+    C: [f64; 3]
+}
+
+/// `MatCharName` is written as text, `Gran` goes to [RegionEntry::gran].
+#[derive(Serialize, Deserialize)]
+#[serde(remote = "TRegion")]
+#[allow(non_snake_case)]
+struct TRegionDef {
+    x: f64,
+    y: f64,
+    R: f64,
+    TriW: bool,
+    CNu: f64,
+    PrMag: u8,
+    Px: f64,
+    Py: f64,
+    W: f64,
+    Ip: f64,
+    ST: f64,
+    PLOT: f64,
+    JbyIW: bool,
+    #[serde(with = "mat_char_name")]
+    MatCharName: [u8; 40],
+    KGran: i32,
+    #[serde(skip, default = "std::ptr::null_mut")]
+    Gran: *mut TGran,
+    vGranMemo: i32,
+    NMatChar: i32
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(remote = "TGran")]
+#[allow(non_snake_case)]
+struct TGranDef {
+    Node: i32,
+    UgUzl: f64
+}
+
+mod mat_char_name {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(name: &[u8; 40], serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&read_mat_char_name(name))
+    }
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<[u8; 40], D::Error> {
+        String::deserialize(deserializer).map(|name| write_mat_char_name(&name))
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct PointEntry {
+    id: EntityId,
+    #[serde(flatten, with = "TBPointDef")]
+    record: TBPoint
+}
+
+#[derive(Serialize, Deserialize)]
+struct PrimEntry {
+    id: EntityId,
+    #[serde(flatten, with = "TPrimitiveDef")]
+    record: TPrimitive
+}
+
+#[derive(Serialize, Deserialize)]
+struct NodeEntry {
+    #[serde(flatten, with = "TNodeDef")]
+    record: TNode,
+    /// `NSW` of the node.
+    links: Vec<i32>
+}
+
+#[derive(Serialize, Deserialize)]
+struct ElemEntry {
+    #[serde(flatten, with = "TElementDef")]
+    record: TElement,
+    /// Bx, By and Bm, absent without a field.
+    #[serde(default)]
+    field: Option<(f64, f64, f64)>
+}
+
+#[derive(Serialize, Deserialize)]
+struct GranEntry(#[serde(with = "TGranDef")] TGran);
+
+#[derive(Serialize, Deserialize)]
+struct RegionEntry {
+    id: EntityId,
+    #[serde(flatten, with = "TRegionDef")]
+    record: TRegion,
+    gran: Vec<GranEntry>
+}
+
+#[derive(Serialize, Deserialize, Default)]
+#[serde(default)]
+struct ProjectModel {
+    points: Vec<PointEntry>,
+    prims: Vec<PrimEntry>,
+    nodes: Vec<NodeEntry>,
+    elems: Vec<ElemEntry>,
+    regions: Vec<RegionEntry>
+}
+
+#[derive(Serialize, Deserialize)]
+struct Project {
+    version: u64,
+    model: ProjectModel,
+    #[serde(default)]
+    view: ViewState
+}
+
+impl Project {
+    fn new(model: &Model, app_config: &AppConfig) -> Self {
+        let id = |entity: Entity, index: usize| model.id(entity, index).unwrap_or_default();
+        let has_field = model.has_field();
+        let project_model = ProjectModel {
+            points: (0..model.points_len())
+                .filter_map(|i| model.tb_point_ref(i).map(|record| PointEntry { id: id(Entity::Point, i), record: record.clone() }))
+                .collect(),
+            prims: (0..model.prims_len())
+                .filter_map(|i| model.t_primitive_ref(i).map(|record| PrimEntry { id: id(Entity::Prim, i), record: record.clone() }))
+                .collect(),
+            nodes: (0..model.nodes_len())
+                .filter_map(|i| model.t_node_ref(i).map(|record| NodeEntry { record: record.detached(), links: model.node_links(i).to_vec() }))
+                .collect(),
+            elems: (0..model.elems_len())
+                .filter_map(|i| model.t_element_ref(i).map(|record| ElemEntry {
+                    record: record.clone(),
+                    field: if has_field { model.get_bx_by_bm(i) } else { None }
+                }))
+                .collect(),
+            regions: (0..model.regions_len())
+                .filter_map(|i| model.t_region_ref(i).map(|record| RegionEntry {
+                    id: id(Entity::Region, i),
+                    record: record.detached(),
+                    gran: model.region_gran(i).iter().cloned().map(GranEntry).collect()
+                }))
+                .collect()
+        };
+        Self { version: PROJECT_VERSION, model: project_model, view: app_config.view_state() }
+    }
+
+    /// Own model with the records and IDs of the file.
+    fn into_model(self) -> (Model, ViewState) {
+        let ProjectModel { points, prims, nodes, elems, regions } = self.model;
+        let has_field = elems.iter().any(|elem| elem.field.is_some());
+        let point_ids: Vec<EntityId> = points.iter().map(|entry| entry.id).collect();
+        let prim_ids: Vec<EntityId> = prims.iter().map(|entry| entry.id).collect();
+        let region_ids: Vec<EntityId> = regions.iter().map(|entry| entry.id).collect();
+
+        let mut own = OwnModel {
+            points: points.into_iter().map(|entry| entry.record).collect(),
+            prims: prims.into_iter().map(|entry| entry.record).collect(),
+            fields: if has_field { elems.iter().map(|elem| elem.field.unwrap_or_default()).collect() } else { vec![] },
+            elems: elems.into_iter().map(|entry| entry.record).collect(),
+            ..OwnModel::default()
+        };
+        for entry in nodes {
+            own.nodes.push(entry.record);
+            own.node_links.push(entry.links);
+        }
+        for entry in regions {
+            own.regions.push(entry.record);
+            own.grans.push(entry.gran.into_iter().map(|gran| gran.0).collect());
+        }
+
+        let mut model = Model::from_store(Box::new(own));
+        for (entity, ids) in [(Entity::Point, point_ids), (Entity::Prim, prim_ids), (Entity::Region, region_ids)] {
+            for (index, id) in ids.into_iter().enumerate().filter(|&(_, id)| id != 0) {
+                model.set_id(entity, index, id)
+            }
+        }
+        (model, self.view)
+    }
+}
+
+/// The path ends with [PROJECT_EXTENSION].
+pub fn is_project(path: &str) -> bool {
+    path.trim().rsplit_once('.').is_some_and(|(_, extension)| extension == PROJECT_EXTENSION)
+}
+
+pub fn save_project(path: &str, model: &Model, app_config: &AppConfig) -> Result<(), String> {
+    let text = write_project(model, app_config)?;
+    std::fs::write(path.trim(), text).map_err(|error| format!("{}: {error}", path.trim()))
+}
+
+/// Reads a project of any known version. The model is an own one, it can be sent to the core afterwards.
+pub fn open_project(path: &str) -> Result<(Model, ViewState), String> {
+    let text = std::fs::read_to_string(path.trim()).map_err(|error| format!("{}: {error}", path.trim()))?;
+    read_project(&text).map_err(|error| format!("{}: {error}", path.trim()))
+}
+
+/// Text of the project file.
+fn write_project(model: &Model, app_config: &AppConfig) -> Result<String, String> {
+    serde_json::to_string_pretty(&Project::new(model, app_config)).map_err(|error| format!("Project is not written: {error}"))
+}
+
+fn read_project(text: &str) -> Result<(Model, ViewState), String> {
+    let value: Value = serde_json::from_str(text).map_err(|error| error.to_string())?;
+    let project: Project = serde_json::from_value(migrate(value)?).map_err(|error| error.to_string())?;
+    Ok(project.into_model())
+}
+
+/// Brings the file to [PROJECT_VERSION].
+fn migrate(mut value: Value) -> Result<Value, String> {
+    let version = value.get("version")
+        .and_then(Value::as_u64)
+        .ok_or_else(|| String::from("No schema version, this is not a vec_red project"))?;
+    if version == 0 || version > PROJECT_VERSION {
+        return Err(format!("Schema version {version} is not supported, this build reads versions 1 to {PROJECT_VERSION}"))
+    }
+    for step in &MIGRATIONS[version as usize - 1..] {
+        step(&mut value)?;
+    }
+    value["version"] = Value::from(PROJECT_VERSION);
+    Ok(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Every record of the model as text, the records have no `PartialEq`.
+    fn records(model: &Model) -> String {
+        let mut text = String::new();
+        for i in 0..model.points_len() {
+            text += &format!("{:?} {:?}\n", model.id(Entity::Point, i), model.tb_point_ref(i));
+        }
+        for i in 0..model.prims_len() {
+            text += &format!("{:?} {:?}\n", model.id(Entity::Prim, i), model.t_primitive_ref(i));
+        }
+        for i in 0..model.nodes_len() {
+            text += &format!("{:?} {:?}\n", model.t_node_ref(i).map(TNode::detached), model.node_links(i));
+        }
+        for i in 0..model.elems_len() {
+            text += &format!("{:?} {:?}\n", model.t_element_ref(i), model.get_bx_by_bm(i));
+        }
+        for i in 0..model.regions_len() {
+            text += &format!("{:?} {:?} {:?}\n", model.id(Entity::Region, i), model.t_region_ref(i).map(TRegion::detached), model.region_gran(i));
+        }
+        text
+    }
+
+    #[test]
+    fn project_round_trip_keeps_every_record_and_the_view() {
+        let mut region = TRegion {
+            x: 30., y: 20., R: 5., TriW: true, CNu: 0.5, PrMag: 3, Px: 1., Py: 1., W: 1., Ip: 0.25, ST: 0., PLOT: 1.1, JbyIW: false,
+            MatCharName: [0; 40], KGran: 3, Gran: std::ptr::null_mut(), vGranMemo: 0, NMatChar: 0
+        };
+        region.set_mat_char_name("steel");
+        let mut own = OwnModel {
+            points: vec![
+                TBPoint { x: 0.1 + 0.2, y: 0., r: 20., TypPoint: 1, Vp: 0.5, NNode: 0, ..Default::default() },
+                TBPoint { x: 100., y: 1e-9, r: 20., NNode: 1, ..Default::default() },
+                TBPoint { x: 0., y: 100., r: 20., NNode: 2, ..Default::default() }
+            ],
+            prims: vec![TPrimitive { p: [0, 1, -1], TypPrim: 2, Vp: 0.25, IsFront: true, Ip: 3. }],
+            nodes: vec![
+                TNode { x: 0.1 + 0.2, y: 0., VP: 0.5, ..Default::default() },
+                TNode { x: 100., y: 1e-9, ..Default::default() },
+                TNode { x: 0., y: 100., TypNode: 1, ..Default::default() }
+            ],
+            elems: vec![TElement { m: [0, 1, 2], IZP: 0, ..Default::default() }],
+            regions: vec![region],
+            grans: vec![(0..3).map(|node| TGran { Node: node, UgUzl: 1.5 }).collect()],
+            fields: vec![(1., -2., 5f64.sqrt())],
+            ..Default::default()
+        };
+        own.link_nodes();
+        let mut model = Model::from_store(Box::new(own));
+        model.set_id(Entity::Point, 2, 40);
+        let mut app_config = AppConfig::default();
+        app_config.points_show = !app_config.points_show;
+
+        let text = write_project(&model, &app_config).unwrap();
+        let (opened, view) = read_project(&text).unwrap();
+        assert_eq!(records(&opened), records(&model));
+        assert_eq!(opened.id(Entity::Point, 2), Some(40));
+        assert!(opened.has_field());
+        assert_eq!(format!("{view:?}"), format!("{:?}", app_config.view_state()));
+    }
+
+    #[test]
+    fn known_versions_are_migrated_and_others_refused() {
+        let migrated = migrate(serde_json::json!({ "version": 1, "model": {} })).unwrap();
+        assert_eq!(migrated["version"], PROJECT_VERSION);
+        assert!(migrated["model"].is_object());
+        for refused in [serde_json::json!({ "version": 0 }), serde_json::json!({ "version": PROJECT_VERSION + 1 }), serde_json::json!({ "model": {} })] {
+            assert!(migrate(refused).is_err());
+        }
+
+        let future = format!("{{\"version\": {}, \"model\": {{}}}}", PROJECT_VERSION + 1);
+        assert!(read_project(&future).is_err_and(|error| error.contains("not supported")));
+    }
+}
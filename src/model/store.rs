@@ -0,0 +1,63 @@
+use std::fmt::Debug;
+use iced::Point;
+use super::borrow_types::*;
+use super::entity_ids::EntityIds;
+
+/// Storage behind [super::Model].
+///
+/// The records are read as slices, every change goes through the methods below,
+/// so a store decides on its own where the records live and who has to be told about a change.
+/// [super::own_model::OwnModel] keeps them in the editor, [super::borrow_model::BorrowModel] in the math core.
+pub(crate) trait ModelStore: Debug {
+    fn points(&self) -> &[TBPoint];
+    fn prims(&self) -> &[TPrimitive];
+    fn nodes(&self) -> &[TNode];
+    /// `NSW` of the node: the nodes it shares an edge with.
+    fn node_links(&self, index: usize) -> &[i32];
+    fn elems(&self) -> &[TElement];
+    fn regions(&self) -> &[TRegion];
+    /// `TRegion::Gran`, empty until the mesh is built.
+    fn region_gran(&self, index: usize) -> &[TGran];
+    /// Bx, By and Bm of the element, `None` without a field.
+    fn field(&self, index: usize) -> Option<(f64, f64, f64)>;
+    /// Bm of the element. A store may have a cheaper way to get it than [ModelStore::field].
+    fn bm(&self, index: usize) -> Option<f32> {
+        self.field(index).map(|field| field.2 as f32)
+    }
+
+    fn ids(&self) -> &EntityIds;
+    fn ids_mut(&mut self) -> &mut EntityIds;
+
+    /// Elements and nodes are there.
+    fn has_mesh(&self) -> bool {
+        !self.elems().is_empty()
+    }
+    /// [ModelStore::field] gives the field of the elements.
+    fn has_field(&self) -> bool;
+    /// Conditions of the prims and parameters of the points and regions can be changed.
+    fn editable_physics(&self) -> bool {
+        true
+    }
+    /// The records live in the math core, which has to stay loaded.
+    fn is_borrowed(&self) -> bool {
+        false
+    }
+
+    /// Every change below is an `Err` if the store can not make it, then the records stay as they were.
+    fn commit_point(&mut self, index: usize, point: &TBPoint) -> Result<(), String>;
+    fn commit_prim(&mut self, index: usize, prim: &TPrimitive) -> Result<(), String>;
+    fn commit_region(&mut self, index: usize, region: &TRegion) -> Result<(), String>;
+    fn point_set(&mut self, index: usize, point: Point, point_r: f32) -> Result<(), String>;
+    /// Point indices of the prims from `index` on are moved up by one.
+    fn points_insert(&mut self, index: usize, record: TBPoint) -> Result<(), String>;
+    /// Point indices of the prims after `index` are moved down by one.
+    /// The prims that use the point have to be deleted before.
+    fn points_remove(&mut self, index: usize) -> Result<(), String>;
+    fn prims_insert(&mut self, index: usize, record: TPrimitive) -> Result<(), String>;
+    fn prims_remove(&mut self, index: usize) -> Result<(), String>;
+
+    /// Deletes every record. [EntityIds] are kept, the model clears them itself.
+    fn clear(&mut self);
+    /// Takes the changes made behind the back of the store, e.g. by a call of the core.
+    fn sync(&mut self) {}
+}
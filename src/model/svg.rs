@@ -0,0 +1,89 @@
+//! SVG export of the view: [Model::draw_model] paints into an SVG document instead of the canvas.
+
+use std::fmt::Write;
+use iced::Color;
+use iced::widget::canvas::Path;
+use iced::widget::canvas::path::lyon_path::Event;
+use crate::app_config::AppConfig;
+use super::painter::Painter;
+use super::Model;
+
+pub const SVG_EXTENSION: &str = "svg";
+
+/// The path ends with [SVG_EXTENSION].
+pub fn is_svg(path: &str) -> bool {
+    path.trim().rsplit_once('.').is_some_and(|(_, extension)| extension.eq_ignore_ascii_case(SVG_EXTENSION))
+}
+
+/// Collects the painted paths as SVG elements.
+#[derive(Default)]
+struct SvgPainter {
+    body: String
+}
+
+/// Path data of `path`. Arcs are already cubic curves in it, the same ones the canvas draws.
+fn path_data(path: &Path) -> String {
+    let mut data = String::new();
+    for event in path.raw().iter() {
+        let _ = match event {
+            Event::Begin { at } => write!(data, "M{:.2} {:.2}", at.x, at.y),
+            Event::Line { to, .. } => write!(data, "L{:.2} {:.2}", to.x, to.y),
+            Event::Quadratic { ctrl, to, .. } => write!(data, "Q{:.2} {:.2} {:.2} {:.2}", ctrl.x, ctrl.y, to.x, to.y),
+            Event::Cubic { ctrl1, ctrl2, to, .. } => {
+                write!(data, "C{:.2} {:.2} {:.2} {:.2} {:.2} {:.2}", ctrl1.x, ctrl1.y, ctrl2.x, ctrl2.y, to.x, to.y)
+            }
+            Event::End { close: true, .. } => write!(data, "Z"),
+            Event::End { close: false, .. } => Ok(())
+        };
+    }
+    data
+}
+
+/// `#rrggbb` and the opacity.
+fn svg_color(color: Color) -> (String, f32) {
+    let [r, g, b, a] = color.into_rgba8();
+    (format!("#{r:02x}{g:02x}{b:02x}"), a as f32 / 255.)
+}
+
+impl Painter for SvgPainter {
+    fn stroke(&mut self, path: &Path, color: Color, width: f32) {
+        let data = path_data(path);
+        if data.is_empty() {
+            return
+        }
+        let (color, opacity) = svg_color(color);
+        let _ = write!(self.body, r#"<path d="{data}" fill="none" stroke="{color}" stroke-width="{width:.2}""#);
+        if opacity < 1. {
+            let _ = write!(self.body, r#" stroke-opacity="{opacity:.3}""#);
+        }
+        self.body.push_str("/>\n");
+    }
+    fn fill(&mut self, path: &Path, color: Color) {
+        let data = path_data(path);
+        if data.is_empty() {
+            return
+        }
+        let (color, opacity) = svg_color(color);
+        let _ = write!(self.body, r#"<path d="{data}" fill="{color}""#);
+        if opacity < 1. {
+            let _ = write!(self.body, r#" fill-opacity="{opacity:.3}""#);
+        }
+        self.body.push_str("/>\n");
+    }
+}
+
+/// Writes what the canvas would show with `app_config`: its zoom, size, shown layers, colors and node mode.
+/// [super::painter::whole_model_view] gives a view of the whole model.
+pub fn save_svg(path: &str, model: &Model, scale: f32, app_config: &AppConfig) -> Result<(), String> {
+    let mut painter = SvgPainter::default();
+    model.draw_model(&mut painter, scale, app_config);
+
+    let size = app_config.model_size;
+    let document = format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{w:.0}\" height=\"{h:.0}\" viewBox=\"0 0 {w:.2} {h:.2}\">\n{body}</svg>\n",
+        w = size.width,
+        h = size.height,
+        body = painter.body
+    );
+    std::fs::write(path.trim(), document).map_err(|error| format!("{}: {error}", path.trim()))
+}
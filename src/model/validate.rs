@@ -0,0 +1,194 @@
+//! Checks of the indices and numbers the editor and the core rely on.
+//!
+//! A bad record, e.g. from a CSV row, would otherwise make the drawing index out of bounds
+//! or crash the core. The drawing skips the records [prim_fits] and [elem_fits] reject.
+
+use std::fmt::{Display, Formatter};
+use iced::Point;
+use super::model::Model;
+
+/// Record a [Diagnostic] is about, with its index.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Subject {
+    Point(usize),
+    Prim(usize),
+    Node(usize),
+    Elem(usize),
+    Region(usize)
+}
+
+impl Display for Subject {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Point(index) => write!(f, "Point {index}"),
+            Self::Prim(index) => write!(f, "Prim {index}"),
+            Self::Node(index) => write!(f, "Node {index}"),
+            Self::Elem(index) => write!(f, "Element {index}"),
+            Self::Region(index) => write!(f, "Region {index}")
+        }
+    }
+}
+
+/// One problem found by [Model::validate].
+#[derive(Debug, Clone)]
+pub struct Diagnostic {
+    pub subject: Subject,
+    pub message: String
+}
+
+impl Display for Diagnostic {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {}", self.subject, self.message)
+    }
+}
+
+/// The first two points of the prim exist, the third one exists or is `-1`.
+pub(super) fn prim_fits(prim: &[i32; 3], points_len: usize) -> bool {
+    let fits = |p: i32| usize::try_from(p).is_ok_and(|p| p < points_len);
+    fits(prim[0]) && fits(prim[1]) && (prim[2] == -1 || fits(prim[2]))
+}
+
+/// Every node of the element exists.
+pub(super) fn elem_fits(elem: &[i32; 3], nodes_len: usize) -> bool {
+    elem.iter().all(|&node| usize::try_from(node).is_ok_and(|node| node < nodes_len))
+}
+
+impl Model {
+    /// Every problem of the model, an empty list if it can be drawn and sent to the core.
+    pub fn validate(&self) -> Vec<Diagnostic> {
+        let mut diagnostics = vec![];
+        let mut report = |subject: Subject, message: String| diagnostics.push(Diagnostic { subject, message });
+
+        for index in 0..self.points_len() {
+            let Some(point) = self.tb_point_ref(index) else {
+                continue
+            };
+            if !(point.x.is_finite() && point.y.is_finite()) {
+                report(Subject::Point(index), format!("position ({}, {}) is not a number", point.x, point.y))
+            }
+            if !point.r.is_finite() || point.r < 0. {
+                report(Subject::Point(index), format!("circle {} is not a positive number", point.r))
+            }
+        }
+
+        let points_len = self.points_len();
+        for index in 0..self.prims_len() {
+            let prim = *self.prims(index);
+            for (slot, &p) in prim.iter().enumerate() {
+                if p == -1 && slot < 2 {
+                    report(Subject::Prim(index), format!("-1 in slot {}, only the third point can be -1", slot + 1))
+                } else if p != -1 && usize::try_from(p).map_or(true, |p| p >= points_len) {
+                    report(Subject::Prim(index), format!("point {p} in slot {} does not exist, there are {points_len} points", slot + 1))
+                }
+            }
+            if prim[2] == -1 {
+                if prim[0] == prim[1] {
+                    report(Subject::Prim(index), format!("line starts and ends in point {}", prim[0]))
+                }
+            } else if prim[0] == prim[1] || prim[0] == prim[2] || prim[1] == prim[2] {
+                report(Subject::Prim(index), format!("arc needs three different points, not {prim:?}"))
+            }
+        }
+
+        let nodes_len = self.nodes_len();
+        for index in 0..nodes_len {
+            if let Some(&link) = self.node_links(index).iter().find(|&&link| usize::try_from(link).map_or(true, |link| link >= nodes_len)) {
+                report(Subject::Node(index), format!("linked node {link} does not exist, there are {nodes_len} nodes"))
+            }
+        }
+        for index in 0..self.elems_len() {
+            let elem = self.elems(index);
+            if !elem_fits(elem, nodes_len) {
+                report(Subject::Elem(index), format!("nodes {elem:?} do not all exist, there are {nodes_len} nodes"))
+            }
+        }
+
+        for index in 0..self.regions_len() {
+            let Some(region) = self.t_region_ref(index) else {
+                continue
+            };
+            if !(region.x.is_finite() && region.y.is_finite()) {
+                report(Subject::Region(index), format!("position ({}, {}) is not a number", region.x, region.y))
+            }
+            if !region.R.is_finite() || region.R < 0. {
+                report(Subject::Region(index), format!("R {} is not a positive number", region.R))
+            }
+            if let Some(gran) = self.region_gran(index).iter().find(|gran| usize::try_from(gran.Node).map_or(true, |node| node >= nodes_len)) {
+                report(Subject::Region(index), format!("boundary node {} does not exist", gran.Node))
+            }
+        }
+        diagnostics
+    }
+
+    /// A place of the subject to zoom to, if any of its points exists.
+    pub fn locate(&self, subject: Subject) -> Option<Point> {
+        let point = |p: i32| usize::try_from(p).ok().filter(|&p| p < self.points_len()).map(|p| self.points(p));
+        let node = |n: i32| usize::try_from(n).ok().filter(|&n| n < self.nodes_len()).map(|n| self.nodes(n));
+        let located = match subject {
+            Subject::Point(index) => (index < self.points_len()).then(|| self.points(index)),
+            Subject::Prim(index) => self.t_primitive_ref(index).and_then(|prim| prim.p.iter().find_map(|&p| point(p))),
+            Subject::Node(index) => (index < self.nodes_len()).then(|| self.nodes(index)),
+            Subject::Elem(index) => self.t_element_ref(index).and_then(|elem| elem.m.iter().find_map(|&n| node(n))),
+            Subject::Region(index) => self.region_marker(index).map(|(center, _)| center)
+        };
+        located.filter(|place| place.x.is_finite() && place.y.is_finite())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::borrow_types::{TBPoint, TElement, TNode, TPrimitive};
+    use crate::model::own_model::OwnModel;
+
+    /// Three points and three nodes, with the given prims and elements.
+    fn model(prims: &[[i32; 3]], elems: &[[i32; 3]]) -> Model {
+        let own = OwnModel {
+            points: [(0., 0.), (1., 0.), (0., 1.)].map(|(x, y)| TBPoint { x, y, r: 1., ..Default::default() }).into(),
+            prims: prims.iter().map(|&p| TPrimitive { p, ..Default::default() }).collect(),
+            nodes: [(0., 0.), (1., 0.), (0., 1.)].map(|(x, y)| TNode { x, y, ..Default::default() }).into(),
+            elems: elems.iter().map(|&m| TElement { m, ..Default::default() }).collect(),
+            ..OwnModel::default()
+        };
+        Model::from_store(Box::new(own))
+    }
+
+    fn subjects(model: &Model) -> Vec<Subject> {
+        model.validate().into_iter().map(|diagnostic| diagnostic.subject).collect()
+    }
+
+    #[test]
+    fn valid_lines_arcs_and_elements_pass() {
+        assert!(model(&[[0, 1, -1], [1, 2, 0]], &[[0, 1, 2]]).validate().is_empty());
+    }
+
+    #[test]
+    fn prim_point_out_of_range_is_reported_and_located_by_the_rest() {
+        let model = model(&[[0, 7, -1]], &[]);
+        let diagnostics = model.validate();
+        assert_eq!(diagnostics.len(), 1);
+        assert!(diagnostics[0].message.contains("point 7 in slot 2 does not exist"));
+        assert!(model.locate(Subject::Prim(0)).is_some());
+    }
+
+    #[test]
+    fn arc_needs_three_distinct_points() {
+        assert_eq!(subjects(&model(&[[0, 1, 0], [2, 2, 1]], &[])), [Subject::Prim(0), Subject::Prim(1)]);
+    }
+
+    #[test]
+    fn minus_one_only_in_the_third_slot() {
+        let diagnostics = model(&[[-1, 1, -1], [0, -1, 2]], &[]).validate();
+        let messages: Vec<_> = diagnostics.iter().map(|diagnostic| diagnostic.message.as_str()).collect();
+        assert_eq!(messages, ["-1 in slot 1, only the third point can be -1", "-1 in slot 2, only the third point can be -1"]);
+    }
+
+    #[test]
+    fn element_with_a_missing_node_is_reported() {
+        let model = model(&[], &[[0, 1, 3], [-2, 5, 9]]);
+        assert_eq!(subjects(&model), [Subject::Elem(0), Subject::Elem(1)]);
+        assert!(model.locate(Subject::Elem(0)).is_some());
+        assert!(model.locate(Subject::Elem(1)).is_none());
+        assert!(model.locate(Subject::Elem(2)).is_none());
+    }
+}
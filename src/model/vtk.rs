@@ -0,0 +1,137 @@
+//! VTK `.vtu` export of the mesh and the field for ParaView.
+//!
+//! Point data is the vector potential `VP` of the nodes, cell data is `Bx`, `By` and `Bm` of the elements
+//! (if the model has a field), the region index `IZP` and the material of the region.
+
+use std::fmt::Write;
+use super::validate::elem_fits;
+use super::Model;
+
+/// `VTK_TRIANGLE`
+const TRIANGLE_CELL: u8 = 5;
+
+/// `<DataArray>` of one value per item, in the ASCII format.
+fn data_array<T: std::fmt::Display>(out: &mut String, kind: &str, name: &str, values: impl Iterator<Item = T>) {
+    let _ = writeln!(out, "<DataArray type=\"{kind}\" Name=\"{name}\" format=\"ascii\">");
+    for value in values {
+        let _ = write!(out, "{value} ");
+    }
+    out.push_str("\n</DataArray>\n");
+}
+
+/// `<DataArray>` of strings: VTK keeps them in ASCII as their byte values, each followed by 0.
+fn string_array(out: &mut String, name: &str, values: impl Iterator<Item = String>) {
+    let _ = writeln!(out, "<DataArray type=\"String\" Name=\"{name}\" format=\"ascii\">");
+    for value in values {
+        for byte in value.bytes() {
+            let _ = write!(out, "{byte} ");
+        }
+        out.push_str("0\n");
+    }
+    out.push_str("</DataArray>\n");
+}
+
+/// Writes the nodes and the triangles that fit them, from an own or a borrowed model.
+pub fn save_vtu(path: &str, model: &Model) -> Result<(), String> {
+    std::fs::write(path.trim(), write_vtu(model)).map_err(|error| format!("{}: {error}", path.trim()))
+}
+
+fn write_vtu(model: &Model) -> String {
+    let nodes_len = model.nodes_len();
+    let elems: Vec<usize> = (0..model.elems_len()).filter(|&index| elem_fits(model.elems(index), nodes_len)).collect();
+    let node = |index: usize| model.t_node_ref(index);
+    let region = |index: usize| model.t_element_ref(index).map_or(-1, |elem| elem.IZP);
+    let field = |index: usize| model.get_bx_by_bm(index).unwrap_or_default();
+
+    let mut out = String::new();
+    out.push_str("<?xml version=\"1.0\"?>\n<VTKFile type=\"UnstructuredGrid\" version=\"1.0\" byte_order=\"LittleEndian\">\n<UnstructuredGrid>\n");
+    let _ = writeln!(out, "<Piece NumberOfPoints=\"{nodes_len}\" NumberOfCells=\"{}\">", elems.len());
+
+    out.push_str("<Points>\n<DataArray type=\"Float64\" NumberOfComponents=\"3\" format=\"ascii\">\n");
+    for index in 0..nodes_len {
+        let (x, y) = node(index).map_or((0., 0.), |node| (node.x, node.y));
+        let _ = writeln!(out, "{x} {y} 0");
+    }
+    out.push_str("</DataArray>\n</Points>\n");
+
+    out.push_str("<Cells>\n");
+    data_array(&mut out, "Int32", "connectivity", elems.iter().flat_map(|&index| *model.elems(index)));
+    data_array(&mut out, "Int32", "offsets", (1..=elems.len()).map(|cell| cell * 3));
+    data_array(&mut out, "UInt8", "types", elems.iter().map(|_| TRIANGLE_CELL));
+    out.push_str("</Cells>\n");
+
+    out.push_str("<PointData Scalars=\"VP\">\n");
+    data_array(&mut out, "Float64", "VP", (0..nodes_len).map(|index| node(index).map_or(0., |node| node.VP)));
+    out.push_str("</PointData>\n");
+
+    if model.has_field() {
+        out.push_str("<CellData Scalars=\"Bm\">\n");
+        data_array(&mut out, "Float64", "Bx", elems.iter().map(|&index| field(index).0));
+        data_array(&mut out, "Float64", "By", elems.iter().map(|&index| field(index).1));
+        data_array(&mut out, "Float64", "Bm", elems.iter().map(|&index| field(index).2));
+    } else {
+        out.push_str("<CellData Scalars=\"Region\">\n");
+    }
+    data_array(&mut out, "Int32", "Region", elems.iter().map(|&index| region(index)));
+    let material = |index: usize| {
+        usize::try_from(region(index)).ok()
+            .and_then(|region| model.t_region_ref(region))
+            .map(|region| region.mat_char_name())
+            .unwrap_or_default()
+    };
+    string_array(&mut out, "Material", elems.iter().map(|&index| material(index)));
+    out.push_str("</CellData>\n");
+
+    out.push_str("</Piece>\n</UnstructuredGrid>\n</VTKFile>\n");
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::borrow_types::{TElement, TNode, TRegion};
+    use crate::model::own_model::OwnModel;
+
+    /// Values of the `<DataArray>` with this name, `None` if there is none.
+    fn values(text: &str, name: &str) -> Option<Vec<f64>> {
+        let start = text.find(&format!("Name=\"{name}\""))?;
+        let body = &text[start..];
+        let body = &body[body.find('>')? + 1..body.find("</DataArray>")?];
+        Some(body.split_whitespace().map(|value| value.parse().unwrap()).collect())
+    }
+
+    #[test]
+    fn vtu_has_the_mesh_the_field_and_the_regions() {
+        let mut region = TRegion {
+            x: 0.5, y: 0.5, R: 0.1, TriW: true, CNu: 0.5, PrMag: 3, Px: 0., Py: 0., W: 0., Ip: 0., ST: 0., PLOT: 1.1, JbyIW: false,
+            MatCharName: [0; 40], KGran: 0, Gran: std::ptr::null_mut(), vGranMemo: 0, NMatChar: 0
+        };
+        region.set_mat_char_name("Fe");
+        let own = OwnModel {
+            nodes: [(0., 0., 0.), (1., 0., 1.), (1., 1., 1.), (0., 1., 0.)].map(|(x, y, vp)| TNode { x, y, VP: vp, ..Default::default() }).into(),
+            elems: [([0, 1, 2], 0), ([0, 2, 3], -1)].map(|(m, region)| TElement { m, IZP: region, ..Default::default() }).into(),
+            regions: vec![region],
+            grans: vec![vec![]],
+            fields: vec![(1., 0., 1.), (0., 2., 2.)],
+            ..OwnModel::default()
+        };
+        let text = write_vtu(&Model::from_store(Box::new(own)));
+
+        assert!(text.contains("NumberOfPoints=\"4\" NumberOfCells=\"2\""));
+        assert_eq!(values(&text, "connectivity").unwrap(), [0., 1., 2., 0., 2., 3.]);
+        assert_eq!(values(&text, "offsets").unwrap(), [3., 6.]);
+        assert_eq!(values(&text, "VP").unwrap(), [0., 1., 1., 0.]);
+        assert_eq!(values(&text, "Bm").unwrap(), [1., 2.]);
+        assert_eq!(values(&text, "Region").unwrap(), [0., -1.]);
+        assert_eq!(values(&text, "Material").unwrap(), [70., 101., 0., 0.]);
+    }
+
+    #[test]
+    fn vtu_of_a_model_without_field_has_no_b() {
+        let text = write_vtu(&Model::default());
+        assert!(text.contains("NumberOfPoints=\"0\" NumberOfCells=\"0\""));
+        assert!(values(&text, "Bm").is_none());
+        assert_eq!(values(&text, "Region"), Some(vec![]));
+    }
+}
+